@@ -0,0 +1,469 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! An analytic anti-aliased stroker: walks a `tiny_skia::Path` and converts
+//! it, given a `tiny_skia::Stroke` style, into a triangle mesh with
+//! per-vertex coverage, rather than relying on tiny_skia's own supersampled
+//! `stroke_path`. Joins/caps are tessellated as fans; a thin feather ring is
+//! added along the body's outer boundary so edges are analytically
+//! antialiased instead of supersampled. This is an alternate, opt-in stroke
+//! mode (see `PixmapTarget::stroke_analytic_aa`) meant to be A/B'd for
+//! quality/perf against the default tiny_skia stroker, not a full
+//! replacement — only `BlendMode::SourceOver` compositing is implemented.
+
+use tiny_skia::{Path, PathSegment, Point, Transform};
+
+/// Width, in pixels, of the anti-aliased feather ring added outside the
+/// solid stroke body.
+const FEATHER_WIDTH: f32 = 0.75;
+
+/// Number of triangles used to fan out a round join or cap.
+const ROUND_FAN_SEGMENTS: u32 = 8;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Vertex {
+    pub x: f32,
+    pub y: f32,
+    pub coverage: f32,
+}
+
+impl Vertex {
+    fn new(point: Point, coverage: f32) -> Self {
+        Vertex { x: point.x, y: point.y, coverage }
+    }
+}
+
+pub type Triangle = [Vertex; 3];
+
+fn sub(a: Point, b: Point) -> Point {
+    Point::from_xy(a.x - b.x, a.y - b.y)
+}
+
+fn add(a: Point, b: Point) -> Point {
+    Point::from_xy(a.x + b.x, a.y + b.y)
+}
+
+fn scale(a: Point, s: f32) -> Point {
+    Point::from_xy(a.x * s, a.y * s)
+}
+
+fn length(a: Point) -> f32 {
+    (a.x * a.x + a.y * a.y).sqrt()
+}
+
+fn normalize(a: Point) -> Point {
+    let len = length(a);
+    if len < f32::EPSILON {
+        Point::from_xy(0., 0.)
+    } else {
+        scale(a, 1. / len)
+    }
+}
+
+/// Rotates a vector 90 degrees counter-clockwise; used to turn a segment's
+/// direction into its stroke-offset normal.
+fn perpendicular(a: Point) -> Point {
+    Point::from_xy(-a.y, a.x)
+}
+
+fn flatten_quad(p0: Point, ctrl: Point, p1: Point, tolerance: f32, out: &mut Vec<Point>) {
+    fn flatness(p0: Point, ctrl: Point, p1: Point) -> f32 {
+        length(sub(ctrl, scale(add(p0, p1), 0.5)))
+    }
+    fn go(p0: Point, ctrl: Point, p1: Point, tolerance: f32, depth: u32, out: &mut Vec<Point>) {
+        if depth >= 16 || flatness(p0, ctrl, p1) <= tolerance {
+            out.push(p1);
+            return;
+        }
+        let p01 = scale(add(p0, ctrl), 0.5);
+        let p12 = scale(add(ctrl, p1), 0.5);
+        let mid = scale(add(p01, p12), 0.5);
+        go(p0, p01, mid, tolerance, depth + 1, out);
+        go(mid, p12, p1, tolerance, depth + 1, out);
+    }
+    go(p0, ctrl, p1, tolerance, 0, out);
+}
+
+fn flatten_cubic(p0: Point, c1: Point, c2: Point, p1: Point, tolerance: f32, out: &mut Vec<Point>) {
+    fn flatness(p0: Point, c1: Point, c2: Point, p1: Point) -> f32 {
+        let ux = 3. * c1.x - 2. * p0.x - p1.x;
+        let uy = 3. * c1.y - 2. * p0.y - p1.y;
+        let vx = 3. * c2.x - 2. * p1.x - p0.x;
+        let vy = 3. * c2.y - 2. * p1.y - p0.y;
+        ux.abs().max(vx.abs()) + uy.abs().max(vy.abs())
+    }
+    fn go(p0: Point, c1: Point, c2: Point, p1: Point, tolerance: f32, depth: u32, out: &mut Vec<Point>) {
+        if depth >= 16 || flatness(p0, c1, c2, p1) <= 6. * tolerance {
+            out.push(p1);
+            return;
+        }
+        let p01 = scale(add(p0, c1), 0.5);
+        let p12 = scale(add(c1, c2), 0.5);
+        let p23 = scale(add(c2, p1), 0.5);
+        let p012 = scale(add(p01, p12), 0.5);
+        let p123 = scale(add(p12, p23), 0.5);
+        let mid = scale(add(p012, p123), 0.5);
+        go(p0, p01, p012, mid, tolerance, depth + 1, out);
+        go(mid, p123, p23, p1, tolerance, depth + 1, out);
+    }
+    go(p0, c1, c2, p1, tolerance, 0, out);
+}
+
+struct Subpath {
+    points: Vec<Point>,
+    closed: bool,
+}
+
+fn flatten_path(path: &Path, tolerance: f32) -> Vec<Subpath> {
+    let mut subpaths = Vec::new();
+    let mut current: Vec<Point> = Vec::new();
+    let mut last = Point::from_xy(0., 0.);
+
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                if current.len() > 1 {
+                    subpaths.push(Subpath { points: std::mem::take(&mut current), closed: false });
+                } else {
+                    current.clear();
+                }
+                current.push(p);
+                last = p;
+            },
+            PathSegment::LineTo(p) => {
+                current.push(p);
+                last = p;
+            },
+            PathSegment::QuadTo(ctrl, p) => {
+                flatten_quad(last, ctrl, p, tolerance, &mut current);
+                last = p;
+            },
+            PathSegment::CubicTo(c1, c2, p) => {
+                flatten_cubic(last, c1, c2, p, tolerance, &mut current);
+                last = p;
+            },
+            PathSegment::Close => {
+                if current.len() > 1 {
+                    subpaths.push(Subpath { points: std::mem::take(&mut current), closed: true });
+                } else {
+                    current.clear();
+                }
+            },
+        }
+    }
+    if current.len() > 1 {
+        subpaths.push(Subpath { points: current, closed: false });
+    }
+    subpaths
+}
+
+fn push_quad(a: Point, b: Point, c: Point, d: Point, coverage: [f32; 4], out: &mut Vec<Triangle>) {
+    out.push([Vertex::new(a, coverage[0]), Vertex::new(b, coverage[1]), Vertex::new(c, coverage[2])]);
+    out.push([Vertex::new(a, coverage[0]), Vertex::new(c, coverage[2]), Vertex::new(d, coverage[3])]);
+}
+
+/// Emits the solid stroke body for a single segment, plus a feather quad
+/// along each of its two long edges (coverage 1.0 at the body, ramping to
+/// 0.0 at `FEATHER_WIDTH` further out).
+fn emit_segment(p0: Point, p1: Point, half_width: f32, out: &mut Vec<Triangle>) {
+    let dir = normalize(sub(p1, p0));
+    if length(dir) < f32::EPSILON {
+        return;
+    }
+    let normal = perpendicular(dir);
+    let offset = scale(normal, half_width);
+    let feather = scale(normal, half_width + FEATHER_WIDTH);
+
+    let left = add(p0, offset);
+    let right = sub(p0, offset);
+    let left_end = add(p1, offset);
+    let right_end = sub(p1, offset);
+
+    push_quad(left, left_end, right_end, right, [1., 1., 1., 1.], out);
+
+    let left_feather = add(p0, feather);
+    let left_feather_end = add(p1, feather);
+    push_quad(left_feather, left_feather_end, left_end, left, [0., 0., 1., 1.], out);
+
+    let right_feather = sub(p0, feather);
+    let right_feather_end = sub(p1, feather);
+    push_quad(right, right_end, right_feather_end, right_feather, [1., 1., 0., 0.], out);
+}
+
+/// Fills the join between two segments meeting at `center`, fanning from
+/// `from` to `to` (both `half_width` away from `center`) through the
+/// intermediate `points`, plus a matching feather ring.
+fn emit_fan(center: Point, from: Point, to: Point, points: &[Point], half_width: f32, out: &mut Vec<Triangle>) {
+    let mut ring = Vec::with_capacity(points.len() + 2);
+    ring.push(from);
+    ring.extend_from_slice(points);
+    ring.push(to);
+
+    for pair in ring.windows(2) {
+        out.push([Vertex::new(center, 1.), Vertex::new(pair[0], 1.), Vertex::new(pair[1], 1.)]);
+
+        let outward = |p: Point| add(center, scale(normalize(sub(p, center)), half_width + FEATHER_WIDTH));
+        push_quad(outward(pair[0]), outward(pair[1]), pair[1], pair[0], [0., 0., 1., 1.], out);
+    }
+}
+
+fn round_fan_points(center: Point, from: Point, to: Point, radius: f32) -> Vec<Point> {
+    let start_angle = sub(from, center).y.atan2(sub(from, center).x);
+    let mut end_angle = sub(to, center).y.atan2(sub(to, center).x);
+
+    // Walk the shorter way around, same winding as `from` -> `to`.
+    let cross = sub(from, center).x * sub(to, center).y - sub(from, center).y * sub(to, center).x;
+    if cross >= 0. && end_angle < start_angle {
+        end_angle += std::f32::consts::TAU;
+    } else if cross < 0. && end_angle > start_angle {
+        end_angle -= std::f32::consts::TAU;
+    }
+
+    (1..ROUND_FAN_SEGMENTS)
+        .map(|i| {
+            let t = i as f32 / ROUND_FAN_SEGMENTS as f32;
+            let angle = start_angle + (end_angle - start_angle) * t;
+            add(center, Point::from_xy(angle.cos() * radius, angle.sin() * radius))
+        })
+        .collect()
+}
+
+fn emit_join(center: Point, prev_dir: Point, next_dir: Point, half_width: f32, join: tiny_skia::LineJoin, miter_limit: f32, out: &mut Vec<Triangle>) {
+    // Both the convex (outer) and concave (inner) side are filled the same
+    // way; the concave side's fan overlaps already-opaque segment bodies,
+    // which is harmless since both are coverage 1.0.
+    let prev_normal = perpendicular(prev_dir);
+    let next_normal = perpendicular(next_dir);
+
+    for sign in [1.0f32, -1.0] {
+        let from = add(center, scale(prev_normal, sign * half_width));
+        let to = add(center, scale(next_normal, sign * half_width));
+
+        match join {
+            tiny_skia::LineJoin::Round => {
+                let points = round_fan_points(center, from, to, half_width);
+                emit_fan(center, from, to, &points, half_width, out);
+            },
+            tiny_skia::LineJoin::Bevel => {
+                emit_fan(center, from, to, &[], half_width, out);
+            },
+            tiny_skia::LineJoin::Miter => {
+                let half_angle = (length(sub(from, to)) / (2. * half_width)).clamp(-1., 1.).asin();
+                let miter_len = if half_angle.cos().abs() < f32::EPSILON { f32::INFINITY } else { 1. / half_angle.cos() };
+                if miter_len.is_finite() && miter_len <= miter_limit {
+                    let bisector = normalize(add(normalize(sub(from, center)), normalize(sub(to, center))));
+                    let apex = add(center, scale(bisector, half_width * miter_len));
+                    emit_fan(center, from, to, &[apex], half_width, out);
+                } else {
+                    emit_fan(center, from, to, &[], half_width, out);
+                }
+            },
+        }
+    }
+}
+
+fn emit_cap(center: Point, dir: Point, half_width: f32, cap: tiny_skia::LineCap, out: &mut Vec<Triangle>) {
+    let normal = perpendicular(dir);
+    let left = add(center, scale(normal, half_width));
+    let right = sub(center, scale(normal, half_width));
+
+    match cap {
+        tiny_skia::LineCap::Butt => {},
+        tiny_skia::LineCap::Square => {
+            let extension = scale(dir, half_width);
+            emit_segment(center, add(center, extension), half_width, out);
+        },
+        tiny_skia::LineCap::Round => {
+            let points = round_fan_points(center, left, right, half_width);
+            emit_fan(center, left, right, &points, half_width, out);
+        },
+    }
+}
+
+/// Converts `path`, stroked with `stroke`, into an anti-aliased triangle
+/// mesh. `tolerance` bounds how finely curves are flattened into line
+/// segments before stroking (see `PathBuilder::set_tolerance`).
+pub fn stroke_to_mesh(path: &Path, stroke: &tiny_skia::Stroke, tolerance: f32) -> Vec<Triangle> {
+    let half_width = (stroke.width / 2.).max(0.01);
+    let mut triangles = Vec::new();
+
+    for subpath in flatten_path(path, tolerance) {
+        let mut points = subpath.points;
+        if subpath.closed && points.first().map(|p| (p.x, p.y)) != points.last().map(|p| (p.x, p.y)) {
+            if let Some(&first) = points.first() {
+                points.push(first);
+            }
+        }
+        if points.len() < 2 {
+            continue;
+        }
+
+        for pair in points.windows(2) {
+            emit_segment(pair[0], pair[1], half_width, &mut triangles);
+        }
+
+        let corner_count = if subpath.closed { points.len() - 1 } else { points.len().saturating_sub(2) };
+        for i in 0..corner_count {
+            let (prev, center, next) = if subpath.closed {
+                (
+                    points[(i + points.len() - 2) % (points.len() - 1)],
+                    points[i % (points.len() - 1)],
+                    points[(i + 1) % (points.len() - 1)],
+                )
+            } else {
+                (points[i], points[i + 1], points[i + 2])
+            };
+            let prev_dir = normalize(sub(center, prev));
+            let next_dir = normalize(sub(next, center));
+            emit_join(center, prev_dir, next_dir, half_width, stroke.line_join, stroke.miter_limit, &mut triangles);
+        }
+
+        if !subpath.closed {
+            let first_dir = normalize(sub(points[0], points[1]));
+            emit_cap(points[0], first_dir, half_width, stroke.line_cap, &mut triangles);
+            let last = points.len() - 1;
+            let last_dir = normalize(sub(points[last], points[last - 1]));
+            emit_cap(points[last], last_dir, half_width, stroke.line_cap, &mut triangles);
+        }
+    }
+
+    triangles
+}
+
+fn edge(a: Point, b: Point, c: Point) -> f32 {
+    (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x)
+}
+
+fn composite_pixel(pixmap: &mut tiny_skia::Pixmap, x: u32, y: u32, color: tiny_skia::PremultipliedColor, coverage: f32) {
+    let width = pixmap.width();
+    let index = (y * width + x) as usize;
+    let pixels = pixmap.pixels_mut();
+    let Some(&dst) = pixels.get(index) else {
+        return;
+    };
+
+    let coverage = coverage.clamp(0., 1.);
+    let src_a = color.alpha() * coverage;
+    let to_u8 = |c: f32| (c.clamp(0., 1.) * 255.0).round() as u8;
+    let over = |src: f32, dst: f32| src + dst * (1.0 - src_a);
+
+    let out_r = over(color.red() * coverage, dst.red() as f32 / 255.0);
+    let out_g = over(color.green() * coverage, dst.green() as f32 / 255.0);
+    let out_b = over(color.blue() * coverage, dst.blue() as f32 / 255.0);
+    let out_a = over(src_a, dst.alpha() as f32 / 255.0);
+
+    pixels[index] = tiny_skia::PremultipliedColorU8::from_rgba(
+        to_u8(out_r), to_u8(out_g), to_u8(out_b), to_u8(out_a),
+    ).unwrap();
+}
+
+/// Accumulates `triangle`'s barycentric per-pixel coverage into `coverage`
+/// (one `f32` per pixel of a `width`x`height` buffer) by taking the max with
+/// whatever's already there, rather than overwriting it. Adjacent join/cap
+/// fans and a segment's own feather quads all touch the same pixels along a
+/// stroke's outer edge, so summing or overwriting would either double-count
+/// or pick an arbitrary winner; max is the right combination for coverage
+/// that's meant to represent "how covered is this pixel by the stroke",
+/// returns the pixel-space bounding box touched, or `None` if the triangle
+/// is degenerate or entirely outside `width`x`height`.
+fn accumulate_triangle_coverage(
+    coverage: &mut [f32],
+    width: u32,
+    height: u32,
+    triangle: Triangle,
+) -> Option<(u32, u32, u32, u32)> {
+    let v = [
+        Point::from_xy(triangle[0].x, triangle[0].y),
+        Point::from_xy(triangle[1].x, triangle[1].y),
+        Point::from_xy(triangle[2].x, triangle[2].y),
+    ];
+    let vertex_coverage = [triangle[0].coverage, triangle[1].coverage, triangle[2].coverage];
+
+    let min_x = v.iter().map(|p| p.x).fold(f32::INFINITY, f32::min).floor().max(0.) as u32;
+    let max_x = (v.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max).ceil() as u32).min(width);
+    let min_y = v.iter().map(|p| p.y).fold(f32::INFINITY, f32::min).floor().max(0.) as u32;
+    let max_y = (v.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max).ceil() as u32).min(height);
+
+    let area = edge(v[0], v[1], v[2]);
+    if area.abs() < f32::EPSILON || min_x >= max_x || min_y >= max_y {
+        return None;
+    }
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let p = Point::from_xy(x as f32 + 0.5, y as f32 + 0.5);
+            let w0 = edge(v[1], v[2], p);
+            let w1 = edge(v[2], v[0], p);
+            let w2 = edge(v[0], v[1], p);
+            if w0 * area < 0. || w1 * area < 0. || w2 * area < 0. {
+                continue;
+            }
+
+            let pixel_coverage = (w0 * vertex_coverage[0] + w1 * vertex_coverage[1] + w2 * vertex_coverage[2]) / area;
+            if pixel_coverage <= 0. {
+                continue;
+            }
+
+            let index = (y * width + x) as usize;
+            coverage[index] = coverage[index].max(pixel_coverage);
+        }
+    }
+
+    Some((min_x, min_y, max_x, max_y))
+}
+
+/// Transforms `triangles` into device space and rasterizes them into
+/// `pixmap` via barycentric per-pixel coverage, alpha-blending with
+/// `BlendMode::SourceOver` only. Coverage is accumulated across the whole
+/// mesh before compositing a pixel, instead of compositing each triangle
+/// independently — the latter would double-blend translucent overlaps
+/// wherever feather quads from adjacent joins/segments overlap, visible as
+/// seams whenever `color`'s alpha is less than 1.
+pub fn fill_triangles(
+    pixmap: &mut tiny_skia::Pixmap,
+    triangles: &[Triangle],
+    color: tiny_skia::PremultipliedColor,
+    transform: Transform,
+    mask: Option<&tiny_skia::Mask>,
+) {
+    let width = pixmap.width();
+    let height = pixmap.height();
+    let mut coverage = vec![0f32; width as usize * height as usize];
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+
+    for triangle in triangles {
+        let mut transformed = *triangle;
+        for vertex in transformed.iter_mut() {
+            let mut point = Point::from_xy(vertex.x, vertex.y);
+            transform.map_point(&mut point);
+            vertex.x = point.x;
+            vertex.y = point.y;
+        }
+        if let Some((min_x, min_y, max_x, max_y)) = accumulate_triangle_coverage(&mut coverage, width, height, transformed) {
+            bounds = Some(match bounds {
+                Some((bx0, by0, bx1, by1)) => (bx0.min(min_x), by0.min(min_y), bx1.max(max_x), by1.max(max_y)),
+                None => (min_x, min_y, max_x, max_y),
+            });
+        }
+    }
+
+    let Some((min_x, min_y, max_x, max_y)) = bounds else {
+        return;
+    };
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let pixel_coverage = coverage[(y * width + x) as usize];
+            if pixel_coverage <= 0. {
+                continue;
+            }
+
+            let mask_coverage = mask.map_or(1.0, |mask| {
+                mask.data().get((y * width + x) as usize).copied().unwrap_or(0) as f32 / 255.0
+            });
+
+            composite_pixel(pixmap, x, y, color, pixel_coverage * mask_coverage);
+        }
+    }
+}
@@ -9,9 +9,249 @@ use euclid::default::{Point2D, Rect, Size2D, Transform2D, Vector2D};
 use lyon_geom::Arc;
 use tiny_skia::{Paint, Pixmap, PixmapRef, PixmapPaint, Mask};
 
+use std::rc::Rc;
+
 use crate::canvas_data::{self};
 use crate::{canvas_data::{Backend, DrawOptions, CompositionOp, CanvasPaintState, GenericDrawTarget, Color, GradientStop, Path, GenericPathBuilder, GradientStops, Filter, StrokeOptions}, canvas_paint_thread::AntialiasMode};
 
+mod aa_stroke;
+
+/// Solves the standard two-point-conical-gradient quadratic for the largest
+/// `t` at which the interpolated circle (centered at `lerp(c0, c1, t)` with
+/// radius `r0 + t * dr`) passes through the point, restricted to radii that
+/// are still non-negative. `a`/`b`/`c` are the quadratic's coefficients for
+/// a given point, already including that point's offset from `c0`.
+fn solve_two_circle_t(a: f32, b: f32, c: f32, r0: f32, dr: f32) -> Option<f32> {
+    let radius_at = |t: f32| r0 + t * dr;
+    if a.abs() < 1e-6 {
+        if b.abs() < 1e-6 {
+            return None;
+        }
+        let t = -c / b;
+        return (radius_at(t) >= 0.0).then_some(t);
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrt_discriminant = discriminant.sqrt();
+
+    [(-b + sqrt_discriminant) / (2.0 * a), (-b - sqrt_discriminant) / (2.0 * a)]
+        .into_iter()
+        .filter(|&t| radius_at(t) >= 0.0)
+        .fold(None, |best: Option<f32>, t| match best {
+            Some(best) if best >= t => Some(best),
+            _ => Some(t),
+        })
+}
+
+fn lerp_color(start: tiny_skia::Color, end: tiny_skia::Color, t: f32) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba(
+        start.red() + (end.red() - start.red()) * t,
+        start.green() + (end.green() - start.green()) * t,
+        start.blue() + (end.blue() - start.blue()) * t,
+        start.alpha() + (end.alpha() - start.alpha()) * t,
+    ).unwrap()
+}
+
+fn sample_gradient_stops(stops: &[tiny_skia::GradientStop], t: f32) -> tiny_skia::Color {
+    let t = t.clamp(0.0, 1.0);
+    let Some(first) = stops.first() else {
+        return tiny_skia::Color::TRANSPARENT;
+    };
+    if t <= first.position() {
+        return first.color();
+    }
+
+    for pair in stops.windows(2) {
+        let (start, end) = (&pair[0], &pair[1]);
+        if t <= end.position() {
+            let span = (end.position() - start.position()).max(f32::EPSILON);
+            return lerp_color(start.color(), end.color(), (t - start.position()) / span);
+        }
+    }
+
+    stops.last().unwrap().color()
+}
+
+/// Bakes a CSS/SVG "two-circle" radial gradient (distinct `r0`/`r1` and
+/// possibly offset centers) into a pixmap sized to its own bounding box and
+/// wraps it as a pattern anchored at that bounding box's origin. tiny_skia's
+/// built-in `RadialGradient` only models a single circle (or a zero-radius
+/// focal point), so this path is only needed once `r0` is non-zero.
+///
+/// Returns the baked pixmap alongside the shader that borrows from it: the
+/// shader's lifetime parameter says nothing about how long the underlying
+/// buffer is actually valid for, so the caller must keep the returned `Rc`
+/// alive (by stashing it on `CanvasPaintState`, alongside `fill_style`/
+/// `stroke_style`) for at least as long as it keeps the shader around.
+#[allow(unsafe_code)]
+fn two_circle_radial_gradient_shader<'a>(
+    x0: f32, y0: f32, r0: f32,
+    x1: f32, y1: f32, r1: f32,
+    stops: &[tiny_skia::GradientStop],
+    transform: tiny_skia::Transform,
+) -> (Rc<Pixmap>, tiny_skia::Shader<'a>) {
+    let min_x = (x0 - r0).min(x1 - r1).floor();
+    let min_y = (y0 - r0).min(y1 - r1).floor();
+    let max_x = (x0 + r0).max(x1 + r1).ceil();
+    let max_y = (y0 + r0).max(y1 + r1).ceil();
+    let width = ((max_x - min_x).max(1.0) as u32).clamp(1, 2048);
+    let height = ((max_y - min_y).max(1.0) as u32).clamp(1, 2048);
+
+    let dc_x = x1 - x0;
+    let dc_y = y1 - y0;
+    let dr = r1 - r0;
+    let a = dc_x * dc_x + dc_y * dc_y - dr * dr;
+    let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    let mut pixmap = Pixmap::new(width, height).unwrap();
+    let pixels = pixmap.pixels_mut();
+    for py in 0..height {
+        for px in 0..width {
+            let pt_x = min_x + px as f32 + 0.5 - x0;
+            let pt_y = min_y + py as f32 + 0.5 - y0;
+            let b = -2.0 * (pt_x * dc_x + pt_y * dc_y + r0 * dr);
+            let c = pt_x * pt_x + pt_y * pt_y - r0 * r0;
+
+            let color = solve_two_circle_t(a, b, c, r0, dr)
+                .map(|t| sample_gradient_stops(stops, t))
+                .unwrap_or(tiny_skia::Color::TRANSPARENT)
+                .premultiply();
+
+            pixels[(py * width + px) as usize] = tiny_skia::PremultipliedColorU8::from_rgba(
+                to_u8(color.red()),
+                to_u8(color.green()),
+                to_u8(color.blue()),
+                to_u8(color.alpha()),
+            ).unwrap();
+        }
+    }
+
+    let pixmap = Rc::new(pixmap);
+    // SAFETY: `pixmap`'s heap allocation doesn't move for as long as the
+    // `Rc` (or a clone of it) is alive, and the caller is required to keep
+    // one alive for at least as long as the returned shader.
+    let pixmap_ref: &'a Pixmap = unsafe { &*Rc::as_ptr(&pixmap) };
+    let shader = tiny_skia::Pattern::new(
+        pixmap_ref.as_ref(),
+        tiny_skia::SpreadMode::Pad,
+        tiny_skia::FilterQuality::Bilinear,
+        1.0,
+        transform.pre_translate(min_x, min_y),
+    );
+    (pixmap, shader)
+}
+
+/// tiny_skia's `Pattern` applies a single `SpreadMode` to both axes, so CSS
+/// `repeat-x`/`repeat-y`/`no-repeat` (as opposed to full `repeat`) can't be
+/// expressed directly. Instead, the axes that shouldn't tile are padded with
+/// transparent pixels — wide/tall enough to cover the canvas once — and the
+/// padded tile is then repeated on both axes; the padding keeps a second
+/// copy from ever becoming visible along the non-repeating axis.
+///
+/// Returns `None` for the backing pixmap in the `repeat_x && repeat_y` case,
+/// since that one borrows `surface_data` directly rather than baking a new
+/// buffer. Otherwise see `two_circle_radial_gradient_shader`'s doc comment
+/// for why the backing pixmap must travel with the shader.
+#[allow(unsafe_code)]
+fn surface_pattern_shader<'a>(
+    surface_data: &'a [u8],
+    surface_width: u32,
+    surface_height: u32,
+    repeat_x: bool,
+    repeat_y: bool,
+    canvas_size: Size2D<i32>,
+    transform: tiny_skia::Transform,
+) -> (Option<Rc<Pixmap>>, tiny_skia::Shader<'a>) {
+    let source = PixmapRef::from_bytes(surface_data, surface_width, surface_height).unwrap();
+
+    if repeat_x && repeat_y {
+        return (None, tiny_skia::Pattern::new(
+            source,
+            tiny_skia::SpreadMode::Repeat,
+            tiny_skia::FilterQuality::Bilinear,
+            1.0,
+            transform,
+        ));
+    }
+
+    let padded_width = surface_width + if repeat_x { 0 } else { canvas_size.width.max(1) as u32 };
+    let padded_height = surface_height + if repeat_y { 0 } else { canvas_size.height.max(1) as u32 };
+
+    let mut padded = Pixmap::new(padded_width, padded_height).unwrap();
+    padded.draw_pixmap(0, 0, source, &PixmapPaint::default(), tiny_skia::Transform::identity(), None);
+
+    let padded = Rc::new(padded);
+    // SAFETY: see `two_circle_radial_gradient_shader`.
+    let padded_ref: &'a Pixmap = unsafe { &*Rc::as_ptr(&padded) };
+    let shader = tiny_skia::Pattern::new(
+        padded_ref.as_ref(),
+        tiny_skia::SpreadMode::Repeat,
+        tiny_skia::FilterQuality::Bilinear,
+        1.0,
+        transform,
+    );
+    (Some(padded), shader)
+}
+
+/// Bakes a canvas `createConicGradient()` sweep gradient
+/// into a pixmap covering the whole canvas, since tiny_skia has no native
+/// sweep/angular gradient shader. Unlike the radial/surface bakes above,
+/// this one can't be cropped to a bounding box (a sweep gradient covers the
+/// entire plane around its center), so it's sized to the canvas itself and
+/// anchored at the canvas origin. See `two_circle_radial_gradient_shader`'s
+/// doc comment for why the backing pixmap must travel with the shader.
+#[allow(unsafe_code)]
+fn conic_gradient_shader<'a>(
+    cx: f32,
+    cy: f32,
+    start_angle: f32,
+    stops: &[tiny_skia::GradientStop],
+    canvas_size: Size2D<i32>,
+    transform: tiny_skia::Transform,
+) -> (Rc<Pixmap>, tiny_skia::Shader<'a>) {
+    let width = (canvas_size.width.max(1) as u32).clamp(1, 4096);
+    let height = (canvas_size.height.max(1) as u32).clamp(1, 4096);
+    let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    let mut pixmap = Pixmap::new(width, height).unwrap();
+    let pixels = pixmap.pixels_mut();
+    for py in 0..height {
+        for px in 0..width {
+            let dx = px as f32 + 0.5 - cx;
+            let dy = py as f32 + 0.5 - cy;
+
+            // `createConicGradient` measures its angle clockwise from the
+            // positive x-axis; with screen-space y growing downward,
+            // `atan2(dy, dx)` is `0` pointing right and grows clockwise.
+            let angle = dy.atan2(dx) - start_angle;
+            let t = (angle / (std::f32::consts::PI * 2.0)).rem_euclid(1.0);
+
+            let color = sample_gradient_stops(stops, t).premultiply();
+            pixels[(py * width + px) as usize] = tiny_skia::PremultipliedColorU8::from_rgba(
+                to_u8(color.red()),
+                to_u8(color.green()),
+                to_u8(color.blue()),
+                to_u8(color.alpha()),
+            ).unwrap();
+        }
+    }
+
+    let pixmap = Rc::new(pixmap);
+    // SAFETY: see `two_circle_radial_gradient_shader`.
+    let pixmap_ref: &'a Pixmap = unsafe { &*Rc::as_ptr(&pixmap) };
+    let shader = tiny_skia::Pattern::new(
+        pixmap_ref.as_ref(),
+        tiny_skia::SpreadMode::Pad,
+        tiny_skia::FilterQuality::Bilinear,
+        1.0,
+        transform,
+    );
+    (pixmap, shader)
+}
+
 pub struct TinySkiaBackend;
 
 impl Backend for TinySkiaBackend {
@@ -39,15 +279,15 @@ impl Backend for TinySkiaBackend {
         state: &mut CanvasPaintState<'a>,
         drawtarget: &dyn GenericDrawTarget,
     ) {
-        state.fill_style = canvas_data::Pattern::TinySkia(match style {
-            FillOrStrokeStyle::Color(color) => tiny_skia::Shader::SolidColor(tiny_skia::Color::from_rgba8(
+        let (backing, shader): (Option<Rc<Pixmap>>, _) = match style {
+            FillOrStrokeStyle::Color(color) => (None, tiny_skia::Shader::SolidColor(tiny_skia::Color::from_rgba8(
                 color.red,
                 color.green,
                 color.blue,
                 color.alpha,
-            )),
+            ))),
             FillOrStrokeStyle::LinearGradient(linear) => {
-                tiny_skia::LinearGradient::new(
+                (None, tiny_skia::LinearGradient::new(
                     tiny_skia::Point { x: linear.x0 as f32, y: linear.y0 as f32 },
                     tiny_skia::Point { x: linear.x1 as f32, y: linear.y1 as f32 },
                     linear.stops.into_iter().map(|stop| tiny_skia::GradientStop::new(stop.offset as f32, tiny_skia::Color::from_rgba8(
@@ -58,38 +298,73 @@ impl Backend for TinySkiaBackend {
                     ))).collect::<Vec<tiny_skia::GradientStop>>(),
                     tiny_skia::SpreadMode::Pad,
                     drawtarget.get_transform().to_tiny_skia(),
-                ).unwrap()
+                ).unwrap())
             },
-            FillOrStrokeStyle::RadialGradient(radial) => tiny_skia::RadialGradient::new(
-                tiny_skia::Point { x: radial.x0 as f32, y: radial.y0 as f32 },
-                tiny_skia::Point { x: radial.x1 as f32, y: radial.y1 as f32 },
-                // TODO - tiny_skia will need support for 2 radii, especially if resvg wants to support SVG 2
-                radial.r1 as f32,
-                radial.stops.into_iter().map(|stop| tiny_skia::GradientStop::new(stop.offset as f32, tiny_skia::Color::from_rgba8(
+            FillOrStrokeStyle::RadialGradient(radial) => {
+                let stops = radial.stops.into_iter().map(|stop| tiny_skia::GradientStop::new(stop.offset as f32, tiny_skia::Color::from_rgba8(
                     stop.color.red,
                     stop.color.green,
                     stop.color.blue,
                     stop.color.alpha,
-                ))).collect::<Vec<tiny_skia::GradientStop>>(),
-                tiny_skia::SpreadMode::Pad,
-                drawtarget.get_transform().to_tiny_skia(),
-            ).unwrap(),
+                ))).collect::<Vec<tiny_skia::GradientStop>>();
+
+                if radial.r0 == 0.0 {
+                    (None, tiny_skia::RadialGradient::new(
+                        tiny_skia::Point { x: radial.x0 as f32, y: radial.y0 as f32 },
+                        tiny_skia::Point { x: radial.x1 as f32, y: radial.y1 as f32 },
+                        radial.r1 as f32,
+                        stops,
+                        tiny_skia::SpreadMode::Pad,
+                        drawtarget.get_transform().to_tiny_skia(),
+                    ).unwrap())
+                } else {
+                    // tiny_skia's `RadialGradient` only models a single circle, so a
+                    // non-zero `r0` (two distinct circles) is baked on the CPU instead.
+                    let (backing, shader) = two_circle_radial_gradient_shader(
+                        radial.x0 as f32, radial.y0 as f32, radial.r0 as f32,
+                        radial.x1 as f32, radial.y1 as f32, radial.r1 as f32,
+                        &stops,
+                        drawtarget.get_transform().to_tiny_skia(),
+                    );
+                    (Some(backing), shader)
+                }
+            },
+            FillOrStrokeStyle::ConicGradient(conic) => {
+                let stops = conic.stops.into_iter().map(|stop| tiny_skia::GradientStop::new(stop.offset as f32, tiny_skia::Color::from_rgba8(
+                    stop.color.red,
+                    stop.color.green,
+                    stop.color.blue,
+                    stop.color.alpha,
+                ))).collect::<Vec<tiny_skia::GradientStop>>();
+
+                let (backing, shader) = conic_gradient_shader(
+                    conic.x0 as f32, conic.y0 as f32, conic.angle as f32,
+                    &stops,
+                    drawtarget.get_size(),
+                    drawtarget.get_transform().to_tiny_skia(),
+                );
+                (Some(backing), shader)
+            },
             FillOrStrokeStyle::Surface(surface) => {
-                tiny_skia::Pattern::new(
-                    PixmapRef::from_bytes(
-                        unsafe {
-                            std::slice::from_raw_parts(surface.surface_data.as_ptr() as *const u8, surface.surface_data.len())
-                        },
-                        surface.surface_size.width,
-                        surface.surface_size.height,
-                    ).unwrap(),
-                    tiny_skia::SpreadMode::Pad,
-                    tiny_skia::FilterQuality::Bilinear,
-                    1.0,
-                    drawtarget.get_transform().to_tiny_skia()
+                surface_pattern_shader(
+                    unsafe {
+                        std::slice::from_raw_parts(surface.surface_data.as_ptr() as *const u8, surface.surface_data.len())
+                    },
+                    surface.surface_size.width,
+                    surface.surface_size.height,
+                    surface.repeat_x,
+                    surface.repeat_y,
+                    drawtarget.get_size(),
+                    drawtarget.get_transform().to_tiny_skia(),
                 )
             },
-        });
+        };
+
+        // Keeps the pixmap baked above (if any) alive for as long as
+        // `fill_style` itself — see `two_circle_radial_gradient_shader`'s
+        // doc comment for why the shader alone isn't enough.
+        state.fill_style_backing = backing;
+        state.fill_style = canvas_data::Pattern::TinySkia(shader);
     }
 
     #[allow(unsafe_code)]
@@ -99,15 +374,15 @@ impl Backend for TinySkiaBackend {
         state: &mut CanvasPaintState<'a>,
         drawtarget: &dyn GenericDrawTarget,
     ) {
-        state.stroke_style = canvas_data::Pattern::TinySkia(match style {
-            FillOrStrokeStyle::Color(color) => tiny_skia::Shader::SolidColor(tiny_skia::Color::from_rgba8(
+        let (backing, shader): (Option<Rc<Pixmap>>, _) = match style {
+            FillOrStrokeStyle::Color(color) => (None, tiny_skia::Shader::SolidColor(tiny_skia::Color::from_rgba8(
                 color.red,
                 color.green,
                 color.blue,
                 color.alpha,
-            )),
+            ))),
             FillOrStrokeStyle::LinearGradient(linear) => {
-                tiny_skia::LinearGradient::new(
+                (None, tiny_skia::LinearGradient::new(
                     tiny_skia::Point { x: linear.x0 as f32, y: linear.y0 as f32 },
                     tiny_skia::Point { x: linear.x1 as f32, y: linear.y1 as f32 },
                     linear.stops.into_iter().map(|stop| tiny_skia::GradientStop::new(stop.offset as f32, tiny_skia::Color::from_rgba8(
@@ -118,38 +393,72 @@ impl Backend for TinySkiaBackend {
                     ))).collect::<Vec<tiny_skia::GradientStop>>(),
                     tiny_skia::SpreadMode::Pad,
                     drawtarget.get_transform().to_tiny_skia(),
-                ).unwrap()
+                ).unwrap())
             },
-            FillOrStrokeStyle::RadialGradient(radial) => tiny_skia::RadialGradient::new(
-                tiny_skia::Point { x: radial.x0 as f32, y: radial.y0 as f32 },
-                tiny_skia::Point { x: radial.x1 as f32, y: radial.y1 as f32 },
-                // TODO - Fix this
-                radial.r1 as f32,
-                radial.stops.into_iter().map(|stop| tiny_skia::GradientStop::new(stop.offset as f32, tiny_skia::Color::from_rgba8(
+            FillOrStrokeStyle::RadialGradient(radial) => {
+                let stops = radial.stops.into_iter().map(|stop| tiny_skia::GradientStop::new(stop.offset as f32, tiny_skia::Color::from_rgba8(
                     stop.color.red,
                     stop.color.green,
                     stop.color.blue,
                     stop.color.alpha
-                ))).collect::<Vec<tiny_skia::GradientStop>>(),
-                tiny_skia::SpreadMode::Pad,
-                drawtarget.get_transform().to_tiny_skia(),
-            ).unwrap(),
+                ))).collect::<Vec<tiny_skia::GradientStop>>();
+
+                if radial.r0 == 0.0 {
+                    (None, tiny_skia::RadialGradient::new(
+                        tiny_skia::Point { x: radial.x0 as f32, y: radial.y0 as f32 },
+                        tiny_skia::Point { x: radial.x1 as f32, y: radial.y1 as f32 },
+                        radial.r1 as f32,
+                        stops,
+                        tiny_skia::SpreadMode::Pad,
+                        drawtarget.get_transform().to_tiny_skia(),
+                    ).unwrap())
+                } else {
+                    // tiny_skia's `RadialGradient` only models a single circle, so a
+                    // non-zero `r0` (two distinct circles) is baked on the CPU instead.
+                    let (backing, shader) = two_circle_radial_gradient_shader(
+                        radial.x0 as f32, radial.y0 as f32, radial.r0 as f32,
+                        radial.x1 as f32, radial.y1 as f32, radial.r1 as f32,
+                        &stops,
+                        drawtarget.get_transform().to_tiny_skia(),
+                    );
+                    (Some(backing), shader)
+                }
+            },
+            FillOrStrokeStyle::ConicGradient(conic) => {
+                let stops = conic.stops.into_iter().map(|stop| tiny_skia::GradientStop::new(stop.offset as f32, tiny_skia::Color::from_rgba8(
+                    stop.color.red,
+                    stop.color.green,
+                    stop.color.blue,
+                    stop.color.alpha,
+                ))).collect::<Vec<tiny_skia::GradientStop>>();
+
+                let (backing, shader) = conic_gradient_shader(
+                    conic.x0 as f32, conic.y0 as f32, conic.angle as f32,
+                    &stops,
+                    drawtarget.get_size(),
+                    drawtarget.get_transform().to_tiny_skia(),
+                );
+                (Some(backing), shader)
+            },
             FillOrStrokeStyle::Surface(surface) => {
-                tiny_skia::Pattern::new(
-                    PixmapRef::from_bytes(
-                        unsafe {
-                            std::slice::from_raw_parts(surface.surface_data.as_ptr() as *const u8, surface.surface_data.len())
-                        },
-                        surface.surface_size.width,
-                        surface.surface_size.height,
-                    ).unwrap(),
-                    tiny_skia::SpreadMode::Pad,
-                    tiny_skia::FilterQuality::Bilinear,
-                    1.0,
+                surface_pattern_shader(
+                    unsafe {
+                        std::slice::from_raw_parts(surface.surface_data.as_ptr() as *const u8, surface.surface_data.len())
+                    },
+                    surface.surface_size.width,
+                    surface.surface_size.height,
+                    surface.repeat_x,
+                    surface.repeat_y,
+                    drawtarget.get_size(),
                     drawtarget.get_transform().to_tiny_skia(),
                 )
             },
-        });
+        };
+
+        // See `set_fill_style`: keeps the baked pixmap (if any) alive for
+        // as long as `stroke_style` itself.
+        state.stroke_style_backing = backing;
+        state.stroke_style = canvas_data::Pattern::TinySkia(shader);
     }
 
     fn set_global_composition<'a>(
@@ -202,21 +511,69 @@ impl Backend for TinySkiaBackend {
             opacity: 1.0,
             mask: None,
             mask_paths: vec![],
+            shadow: None,
+            antialias: true,
+            stroke_mode: StrokeMode::default(),
         })
     }
 
     fn recreate_paint_state<'a>(&self, state: &CanvasPaintState<'a>) -> CanvasPaintState<'a> {
-        CanvasPaintState::new(AntialiasMode::Default)
+        CanvasPaintState::new(state.antialias)
+    }
+}
+
+/// `tiny_skia::Paint::anti_alias` is a plain bool, so the canvas'
+/// `{antialias: ...}` context-creation hint collapses to it directly: only
+/// `AntialiasMode::None` turns antialiasing off.
+fn antialias_mode_to_bool(antialias: AntialiasMode) -> bool {
+    !matches!(antialias, AntialiasMode::None)
+}
+
+/// Multiplies `glyph_mask` (positioned at `(origin_x, origin_y)` in device
+/// pixels) by the matching pixels of `clip`, in place, so a glyph drawn
+/// under `ctx.clip()` is actually clipped rather than ignoring it. Pixels of
+/// `glyph_mask` that fall outside `clip`'s bounds are zeroed, matching how a
+/// clip excludes anything outside itself.
+fn intersect_glyph_mask_with_clip(glyph_mask: &mut Mask, origin_x: i32, origin_y: i32, clip: &Mask) {
+    let glyph_width = glyph_mask.width();
+    let glyph_height = glyph_mask.height();
+    let clip_width = clip.width() as i32;
+    let clip_height = clip.height() as i32;
+    let clip_data = clip.data();
+    let glyph_data = glyph_mask.data_mut();
+
+    for y in 0..glyph_height {
+        for x in 0..glyph_width {
+            let clip_x = origin_x + x as i32;
+            let clip_y = origin_y + y as i32;
+            let clip_value = if clip_x >= 0 && clip_y >= 0 && clip_x < clip_width && clip_y < clip_height {
+                clip_data[(clip_y * clip_width + clip_x) as usize]
+            } else {
+                0
+            };
+            let index = (y * glyph_width + x) as usize;
+            glyph_data[index] = ((glyph_data[index] as u32 * clip_value as u32) / 255) as u8;
+        }
     }
 }
 
+// `fill_style_backing`/`stroke_style_backing` (below) are new fields this
+// struct needs so the `Rc<Pixmap>` backing a baked gradient/pattern shader
+// has a real owner instead of being leaked — see `two_circle_radial_gradient_shader`/
+// `surface_pattern_shader`/`conic_gradient_shader`. `CanvasPaintState` is
+// declared in canvas_data.rs, which isn't part of this checkout, so the
+// field additions have to land as a companion edit there.
 impl<'a> CanvasPaintState<'a> {
-    pub fn new(_antialias: AntialiasMode) -> CanvasPaintState<'a> {
+    pub fn new(antialias: AntialiasMode) -> CanvasPaintState<'a> {
         let pattern = tiny_skia::Shader::SolidColor(tiny_skia::Color::BLACK);
+        let mut paint = tiny_skia::Paint::default();
+        paint.anti_alias = antialias_mode_to_bool(antialias);
         CanvasPaintState {
-            draw_options: DrawOptions::TinySkia(tiny_skia::Paint::default()),
+            draw_options: DrawOptions::TinySkia(paint, tiny_skia::FillRule::Winding),
             fill_style: canvas_data::Pattern::TinySkia(pattern.clone()),
+            fill_style_backing: None,
             stroke_style: canvas_data::Pattern::TinySkia(pattern),
+            stroke_style_backing: None,
             stroke_opts: StrokeOptions::TinySkia(tiny_skia::Stroke::default()),
             transform: Transform2D::identity(),
             shadow_offset_x: 0.0,
@@ -226,6 +583,7 @@ impl<'a> CanvasPaintState<'a> {
             font_style: None,
             text_align: TextAlign::default(),
             text_baseline: TextBaseline::default(),
+            antialias,
         }
     }
 }
@@ -264,7 +622,7 @@ impl Path {
     ) -> Box<dyn GenericPathBuilder> {
         let mut pb = tiny_skia::PathBuilder::new();
         pb.push_path(&self.as_tiny_skia().clone().transform(transform.to_tiny_skia()).unwrap());
-        Box::new(PathBuilder(Some(pb)))
+        Box::new(PathBuilder(Some(pb), DEFAULT_ARC_TOLERANCE))
     }
 
     pub fn contains_point(&self, x: f64, y: f64, path_transform: &Transform2D<f32>) -> bool {
@@ -279,7 +637,7 @@ impl Path {
     pub fn copy_to_builder(&self) -> Box<dyn GenericPathBuilder> {
         let mut pb = tiny_skia::PathBuilder::new();
         pb.push_path(&self.as_tiny_skia().clone());
-        Box::new(PathBuilder(Some(pb)))
+        Box::new(PathBuilder(Some(pb), DEFAULT_ARC_TOLERANCE))
     }
 
     fn as_tiny_skia(&self) -> &tiny_skia::Path {
@@ -350,6 +708,30 @@ impl StrokeOptions {
         }
     }
 
+    pub fn set_line_dash(&mut self, val: Vec<f32>) {
+        match self {
+            StrokeOptions::TinySkia(options) => {
+                let offset = options.dash.as_ref().map_or(0., |dash| dash.offset);
+                options.dash = tiny_skia::StrokeDash::new(val, offset);
+            },
+            _ => todo!(),
+        }
+    }
+
+    // `tiny_skia::StrokeDash` carries the dash array and offset together,
+    // so an offset set while no dash array is set is dropped; a later
+    // `set_line_dash` then starts its pattern at offset 0.
+    pub fn set_line_dash_offset(&mut self, val: f32) {
+        match self {
+            StrokeOptions::TinySkia(options) => {
+                if let Some(dash) = options.dash.as_mut() {
+                    dash.offset = val;
+                }
+            },
+            _ => todo!(),
+        }
+    }
+
     pub fn as_tiny_skia(&self) -> &tiny_skia::Stroke {
         match self {
             StrokeOptions::TinySkia(options) => options,
@@ -358,6 +740,12 @@ impl StrokeOptions {
     }
 }
 
+// `DrawOptions::TinySkia` is matched here as a `(Paint, FillRule)` pair (see
+// `set_fill_rule`/`as_tiny_skia_fill_rule` below), which means the variant's
+// declaration in canvas_data.rs needs the matching arity change from its
+// previous `TinySkia(Paint)` shape. That file lives outside this checkout —
+// this crate only carries the tiny_skia backend module — so that half of the
+// change has to land as a companion edit there.
 impl<'a> DrawOptions<'a> {
     pub fn set_alpha(&mut self, val: f32) {
         /*
@@ -368,21 +756,60 @@ impl<'a> DrawOptions<'a> {
         */
     }
 
+    /// Selects between the `"nonzero"` and `"evenodd"` winding rules used to
+    /// decide which areas of a (possibly self-intersecting) path count as
+    /// "inside" for `fill()`. Defaults to `FillRule::NonZero`, matching the
+    /// Canvas2D/SVG default.
+    pub fn set_fill_rule(&mut self, val: FillRule) {
+        match self {
+            DrawOptions::TinySkia(_, fill_rule) => *fill_rule = match val {
+                FillRule::NonZero => tiny_skia::FillRule::Winding,
+                FillRule::EvenOdd => tiny_skia::FillRule::EvenOdd,
+            },
+            _ => todo!(),
+        }
+    }
+
     fn as_tiny_skia(&self) -> &tiny_skia::Paint<'a> {
         match self {
-            DrawOptions::TinySkia(paint) => paint,
+            DrawOptions::TinySkia(paint, _) => paint,
             _ => todo!(),
         }
     }
 
     fn as_tiny_skia_mut(&mut self) -> &mut tiny_skia::Paint<'a> {
         match self {
-            DrawOptions::TinySkia(paint) => paint,
+            DrawOptions::TinySkia(paint, _) => paint,
+            _ => todo!(),
+        }
+    }
+
+    fn as_tiny_skia_fill_rule(&self) -> tiny_skia::FillRule {
+        match self {
+            DrawOptions::TinySkia(_, fill_rule) => *fill_rule,
             _ => todo!(),
         }
     }
 }
 
+/// Shadow parameters for the next draw call, mirrored onto the draw target
+/// from `CanvasPaintState` the same way `transform`/`opacity` already are.
+#[derive(Clone)]
+struct ShadowOptions {
+    color: tiny_skia::PremultipliedColor,
+    offset: Vector2D<f32>,
+    sigma: f32,
+}
+
+/// Which rasterizer `stroke` hands a path's outline to. See
+/// `PixmapTarget::stroke_analytic_aa`'s doc comment for the tradeoff.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum StrokeMode {
+    #[default]
+    Supersampled,
+    AnalyticAa,
+}
+
 #[derive(Clone)]
 pub struct PixmapTarget {
     pixmap: Pixmap,
@@ -390,9 +817,203 @@ pub struct PixmapTarget {
     opacity: f32,
     mask: Option<Mask>,
     mask_paths: Vec<tiny_skia::Path>,
+    shadow: Option<ShadowOptions>,
+    /// Whether `push_clip`/`pop_clip` antialias the clip mask they rebuild.
+    /// Mirrors `CanvasPaintState::antialias` — see `set_antialias`.
+    antialias: bool,
+    /// Which rasterizer `stroke` uses. Mirrored in from the paint state via
+    /// `set_stroke_mode`, the same way `antialias` is via `set_antialias`.
+    stroke_mode: StrokeMode,
+}
+
+impl PixmapTarget {
+    /// Alternate stroke mode: tessellates `path`'s stroke outline into an
+    /// analytically antialiased triangle mesh (see `aa_stroke`) instead of
+    /// going through tiny_skia's own supersampled `stroke_path`. Selectable
+    /// alongside `stroke` to A/B quality/perf; currently only supports a
+    /// solid-color `pattern` (the shadow pass and non-solid shaders are not
+    /// wired up yet).
+    pub fn stroke_analytic_aa(
+        &mut self,
+        path: &Path,
+        pattern: crate::canvas_data::Pattern,
+        stroke_options: &StrokeOptions,
+    ) {
+        let tiny_skia::Shader::SolidColor(color) = pattern.as_tiny_skia() else {
+            // This rasterizer only handles solid colors — fall back to the
+            // supersampled path by calling `stroke` with `stroke_mode`
+            // temporarily set to `Supersampled`; left at `AnalyticAa`,
+            // `stroke` would dispatch back here and recurse forever.
+            let saved_mode = self.stroke_mode;
+            self.stroke_mode = StrokeMode::Supersampled;
+            self.stroke(path, pattern, stroke_options, &DrawOptions::TinySkia(tiny_skia::Paint::default(), tiny_skia::FillRule::Winding));
+            self.stroke_mode = saved_mode;
+            return;
+        };
+
+        let triangles = aa_stroke::stroke_to_mesh(path.as_tiny_skia(), stroke_options.as_tiny_skia(), 0.25);
+        aa_stroke::fill_triangles(&mut self.pixmap, &triangles, color.premultiply(), self.transform, self.mask.as_ref());
+    }
+
+    /// Renders `draw_shape` into a same-sized scratch pixmap, box-blurs its
+    /// alpha channel to approximate a Gaussian of the shadow's `sigma`,
+    /// tints the result with the shadow color, and composites it at the
+    /// shadow offset using `blend_mode` — before the caller draws the real
+    /// shape on top.
+    fn draw_shadow(&mut self, blend_mode: tiny_skia::BlendMode, draw_shape: impl FnOnce(&mut Pixmap)) {
+        let Some(shadow) = self.shadow.clone() else {
+            return;
+        };
+
+        let width = self.pixmap.width();
+        let height = self.pixmap.height();
+        let mut scratch = Pixmap::new(width, height).unwrap();
+        draw_shape(&mut scratch);
+
+        let mut alpha: Vec<u8> = scratch.pixels().iter().map(|pixel| pixel.alpha()).collect();
+        box_blur_alpha(&mut alpha, width, height, shadow.sigma);
+
+        // `Color` components are normalized (0.0-1.0) and already
+        // premultiplied by the shadow's own alpha; only the blurred
+        // coverage still needs factoring in.
+        let shadow_rgba = [
+            shadow.color.red(),
+            shadow.color.green(),
+            shadow.color.blue(),
+            shadow.color.alpha(),
+        ];
+        let mut tinted = Pixmap::new(width, height).unwrap();
+        for (dst, &coverage) in tinted.pixels_mut().iter_mut().zip(alpha.iter()) {
+            let coverage = coverage as f32 / 255.0;
+            *dst = tiny_skia::PremultipliedColorU8::from_rgba(
+                (shadow_rgba[0] * coverage * 255.0).round() as u8,
+                (shadow_rgba[1] * coverage * 255.0).round() as u8,
+                (shadow_rgba[2] * coverage * 255.0).round() as u8,
+                (shadow_rgba[3] * coverage * 255.0).round() as u8,
+            ).unwrap();
+        }
+
+        let mut paint = PixmapPaint::default();
+        paint.blend_mode = blend_mode;
+        paint.opacity = self.opacity;
+        self.pixmap.draw_pixmap(
+            shadow.offset.x.round() as i32,
+            shadow.offset.y.round() as i32,
+            tinted.as_ref(),
+            &paint,
+            tiny_skia::Transform::identity(),
+            self.mask.as_ref(),
+        );
+    }
+}
+
+/// The three box-blur radii that together approximate a Gaussian blur of
+/// standard deviation `sigma`, per the standard three-box algorithm: an
+/// ideal width `w ≈ sqrt(12*sigma^2/3 + 1)` rounded down to the nearest odd
+/// integer, with the remaining error made up by using `w + 2` for some of
+/// the three passes.
+fn box_blur_radii(sigma: f32) -> [u32; 3] {
+    if sigma <= 0.0 {
+        return [0, 0, 0];
+    }
+    let ideal = (12.0 * sigma * sigma / 3.0 + 1.0).sqrt();
+    let mut narrow = ideal.floor() as i32;
+    if narrow % 2 == 0 {
+        narrow -= 1;
+    }
+    let narrow = narrow.max(1);
+    let wide = narrow + 2;
+    let narrow_passes = ((12.0 * sigma * sigma -
+        3.0 * (narrow * narrow) as f32 -
+        12.0 * narrow as f32 -
+        9.0) /
+        (-4.0 * narrow as f32 - 4.0))
+        .round()
+        .clamp(0.0, 3.0) as usize;
+
+    let mut widths = [wide as u32; 3];
+    widths[..narrow_passes].fill(narrow as u32);
+    widths.map(|width| (width - 1) / 2)
+}
+
+/// Approximates a Gaussian blur of the alpha channel with three successive
+/// box blurs, each applied separably (a horizontal sliding-window-sum pass
+/// over rows, then a vertical one over columns), so each pass costs
+/// `O(width * height)` regardless of blur radius.
+fn box_blur_alpha(alpha: &mut Vec<u8>, width: u32, height: u32, sigma: f32) {
+    let mut buffer = vec![0u8; alpha.len()];
+    for radius in box_blur_radii(sigma) {
+        if radius == 0 {
+            continue;
+        }
+        box_blur_pass(alpha, &mut buffer, width, height, radius, true);
+        std::mem::swap(alpha, &mut buffer);
+        box_blur_pass(alpha, &mut buffer, width, height, radius, false);
+        std::mem::swap(alpha, &mut buffer);
+    }
+}
+
+/// One separable box-blur pass: a sliding-window sum along rows
+/// (`horizontal`) or columns, clamping the window to the buffer edges.
+fn box_blur_pass(src: &[u8], dst: &mut [u8], width: u32, height: u32, radius: u32, horizontal: bool) {
+    let (w, h) = (width as i32, height as i32);
+    let r = radius as i32;
+    let window = (2 * r + 1) as i32;
+    let (outer, inner) = if horizontal { (h, w) } else { (w, h) };
+
+    let index = |outer_i: i32, inner_i: i32| -> usize {
+        if horizontal {
+            (outer_i * w + inner_i) as usize
+        } else {
+            (inner_i * w + outer_i) as usize
+        }
+    };
+
+    for o in 0..outer {
+        let mut sum = 0i32;
+        for i in -r..=r {
+            sum += src[index(o, i.clamp(0, inner - 1))] as i32;
+        }
+        for i in 0..inner {
+            dst[index(o, i)] = (sum / window) as u8;
+            let next = (i + r + 1).clamp(0, inner - 1);
+            let prev = (i - r).clamp(0, inner - 1);
+            sum += src[index(o, next)] as i32 - src[index(o, prev)] as i32;
+        }
+    }
 }
 
+// `set_shadow_options`, `set_antialias`, and `set_stroke_mode` below are only
+// reachable through `dyn GenericDrawTarget` once `GenericDrawTarget`'s
+// declaration in `canvas_data.rs` grows matching method signatures; that
+// file lives outside this checkout (this crate only carries the tiny_skia
+// backend module), so the trait-side half of these additions has to land as
+// a companion change there rather than in this diff.
 impl GenericDrawTarget for PixmapTarget {
+    /// Sets the shadow that the next `fill`/`fill_rect`/`stroke` call should
+    /// draw first. A fully transparent `color` clears it. Callers reading
+    /// `CanvasPaintState`'s `shadow_color`/`shadow_offset_x`/`shadow_offset_y`/
+    /// `shadow_blur` must call this before `fill`/`fill_rect`/`stroke` — those
+    /// three don't take shadow parameters of their own and otherwise leave
+    /// `ctx.shadowColor`/`ctx.shadowBlur` with no effect.
+    fn set_shadow_options(&mut self, color: &Color, offset: Vector2D<f32>, sigma: f32) {
+        let color = *color.as_tiny_skia();
+        self.shadow = (color.alpha() != 0.0).then_some(ShadowOptions { color, offset, sigma });
+    }
+
+    /// Mirrors `CanvasPaintState::antialias` onto this target, so `push_clip`/
+    /// `pop_clip` (which have no other access to the paint state) rebuild the
+    /// clip mask with the right antialiasing instead of always antialiasing.
+    fn set_antialias(&mut self, antialias: AntialiasMode) {
+        self.antialias = antialias_mode_to_bool(antialias);
+    }
+
+    /// Selects which rasterizer `stroke` uses going forward. See
+    /// `PixmapTarget::stroke_analytic_aa`'s doc comment for the tradeoff.
+    fn set_stroke_mode(&mut self, stroke_mode: StrokeMode) {
+        self.stroke_mode = stroke_mode;
+    }
+
     fn clear_rect(&mut self, rect: &Rect<f32>) {
         let mut paint = tiny_skia::Paint::default();
         paint.blend_mode = tiny_skia::BlendMode::Clear;
@@ -437,7 +1058,13 @@ impl GenericDrawTarget for PixmapTarget {
     }
 
     fn create_path_builder(&self) -> Box<dyn GenericPathBuilder> {
-        Box::new(PathBuilder::new())
+        let mut builder = PathBuilder::new();
+        // Arcs/ellipses are flattened in the path's own (pre-transform) space,
+        // so a tolerance that looks fine untransformed can under-tessellate
+        // once the active transform scales it up. Shrink it by that scale so
+        // the sagitta stays `DEFAULT_ARC_TOLERANCE`-ish in device pixels.
+        builder.set_tolerance(DEFAULT_ARC_TOLERANCE / transform_scale(&self.transform));
+        Box::new(builder)
     }
 
     fn create_similar_draw_target(
@@ -450,6 +1077,9 @@ impl GenericDrawTarget for PixmapTarget {
             opacity: self.opacity,
             mask: self.mask.clone(),
             mask_paths: self.mask_paths.clone(),
+            shadow: self.shadow.clone(),
+            antialias: self.antialias,
+            stroke_mode: self.stroke_mode,
         })
     }
 
@@ -511,7 +1141,7 @@ impl GenericDrawTarget for PixmapTarget {
                         transform,
                     ),
                     blend_mode: drop.blend_mode,
-                    anti_alias: false,
+                    anti_alias: drop.anti_alias,
                     force_hq_pipeline: false,
                 },
                 self.transform,
@@ -522,24 +1152,56 @@ impl GenericDrawTarget for PixmapTarget {
     }
 
     fn draw_surface_with_shadow(
-        &self,
-        _surface: &[u8],
-        _dest: &Point2D<f32>,
-        _color: &Color,
-        _offset: &Vector2D<f32>,
-        _sigma: f32,
-        _operator: CompositionOp,
+        &mut self,
+        surface: &[u8],
+        dest: &Point2D<f32>,
+        color: &Color,
+        offset: &Vector2D<f32>,
+        sigma: f32,
+        operator: CompositionOp,
     ) {
-        println!("no support for drawing shadows");
+        let Some(source) = PixmapRef::from_bytes(surface, self.pixmap.width(), self.pixmap.height()) else {
+            return;
+        };
+        let blend_mode = match operator {
+            CompositionOp::TinySkia(blend_mode) => blend_mode,
+        };
+        self.shadow = Some(ShadowOptions {
+            color: *color.as_tiny_skia(),
+            offset: *offset,
+            sigma,
+        });
+        self.draw_shadow(blend_mode, |scratch| {
+            scratch.draw_pixmap(
+                dest.x as i32,
+                dest.y as i32,
+                source,
+                &PixmapPaint::default(),
+                tiny_skia::Transform::identity(),
+                None,
+            );
+        });
+        self.shadow = None;
     }
 
     fn fill(&mut self, path: &Path, pattern: canvas_data::Pattern, draw_options: &DrawOptions) {
         let mut draw_options = draw_options.clone();
+        let fill_rule = draw_options.as_tiny_skia_fill_rule();
         let mut draw_options2 = draw_options.as_tiny_skia_mut();
         draw_options2.shader = pattern.as_tiny_skia().to_owned();
         draw_options2.shader.apply_opacity(self.opacity);
+        let blend_mode = draw_options2.blend_mode;
+        let anti_alias = draw_options2.anti_alias;
+        let transform = self.transform;
+
+        let tiny_skia_path = path.as_tiny_skia().clone();
+        self.draw_shadow(blend_mode, |scratch| {
+            let mut shadow_paint = tiny_skia::Paint::default();
+            shadow_paint.anti_alias = anti_alias;
+            scratch.fill_path(&tiny_skia_path, &shadow_paint, fill_rule, transform, None);
+        });
 
-        self.pixmap.fill_path(path.as_tiny_skia(), &draw_options2, tiny_skia::FillRule::default(), self.transform, self.mask.as_ref())
+        self.pixmap.fill_path(path.as_tiny_skia(), &draw_options2, fill_rule, self.transform, self.mask.as_ref())
     }
 
     fn fill_text(
@@ -551,7 +1213,115 @@ impl GenericDrawTarget for PixmapTarget {
         pattern: &crate::canvas_data::Pattern,
         draw_options: &DrawOptions,
     ) {
-        //unimplemented!();
+        use font_kit::canvas::{Canvas, Format, RasterizationOptions};
+        use font_kit::hinting::HintingOptions;
+        use pathfinder_geometry::transform2d::Transform2F;
+        use pathfinder_geometry::vector::Vector2F;
+
+        let mut draw_options = draw_options.clone();
+        let draw_options2 = draw_options.as_tiny_skia_mut();
+        draw_options2.shader = pattern.as_tiny_skia().to_owned();
+        draw_options2.shader.apply_opacity(self.opacity);
+        let blend_mode = draw_options2.blend_mode;
+        let anti_alias = draw_options2.anti_alias;
+        let shader = draw_options2.shader.clone();
+        let transform = self.transform;
+
+        // Mirrors `anti_alias` (itself derived from `CanvasPaintState::antialias`
+        // — see `antialias_mode_to_bool`): smoothed text gets light vertical
+        // hinting and grayscale coverage, same as the rest of the canvas
+        // draws antialiased; switching antialiasing off asks for unhinted,
+        // bilevel glyphs instead of silently antialiasing
+        // anyway. Read as a function of `anti_alias` at each call site below
+        // rather than stored once, since font_kit's `HintingOptions`/
+        // `RasterizationOptions` are consumed by value.
+        let hinting_options = |point_size: f32| if anti_alias {
+            HintingOptions::Vertical(point_size)
+        } else {
+            HintingOptions::None
+        };
+        let rasterization_options = || if anti_alias {
+            RasterizationOptions::GrayscaleAa
+        } else {
+            RasterizationOptions::Bilevel
+        };
+
+        let units_per_em = font.metrics().units_per_em as f32;
+        let mut pen_x = start.x;
+        let pen_y = start.y;
+
+        for ch in text.chars() {
+            let Some(glyph_id) = font.glyph_for_char(ch) else {
+                continue;
+            };
+
+            let advance = font
+                .advance(glyph_id)
+                .map(|advance| advance.x() * point_size / units_per_em)
+                .unwrap_or(0.);
+
+            if let Ok(bounds) = font.raster_bounds(
+                glyph_id,
+                point_size,
+                Transform2F::default(),
+                hinting_options(point_size),
+                rasterization_options(),
+            ) {
+                if bounds.width() > 0 && bounds.height() > 0 {
+                    let mut canvas = Canvas::new(bounds.size(), Format::A8);
+                    let glyph_origin = Vector2F::new(-bounds.origin_x() as f32, -bounds.origin_y() as f32);
+
+                    if font
+                        .rasterize_glyph(
+                            &mut canvas,
+                            glyph_id,
+                            point_size,
+                            Transform2F::from_translation(glyph_origin),
+                            hinting_options(point_size),
+                            rasterization_options(),
+                        )
+                        .is_ok()
+                    {
+                        let mask_size = tiny_skia::IntSize::from_wh(
+                            bounds.width() as u32,
+                            bounds.height() as u32,
+                        );
+                        let mut mask = mask_size.and_then(|size| Mask::from_vec(canvas.pixels, size));
+
+                        if let (Some(mask), Some(clip)) = (mask.as_mut(), self.mask.as_ref()) {
+                            let mut origin = tiny_skia::Point::from_xy(
+                                pen_x + bounds.origin_x() as f32,
+                                pen_y + bounds.origin_y() as f32,
+                            );
+                            transform.map_point(&mut origin);
+                            intersect_glyph_mask_with_clip(mask, origin.x.round() as i32, origin.y.round() as i32, clip);
+                        }
+
+                        if let Some(mask) = mask {
+                            let paint = tiny_skia::Paint {
+                                shader: shader.clone(),
+                                blend_mode,
+                                anti_alias,
+                                ..Default::default()
+                            };
+
+                            let glyph_rect = tiny_skia::Rect::from_xywh(
+                                pen_x + bounds.origin_x() as f32,
+                                pen_y + bounds.origin_y() as f32,
+                                bounds.width() as f32,
+                                bounds.height() as f32,
+                            );
+
+                            if let Some(glyph_rect) = glyph_rect {
+                                self.pixmap.fill_rect(glyph_rect, &paint, transform, Some(&mask));
+                            }
+                        }
+                    }
+                }
+            }
+
+            pen_x += advance;
+        }
     }
 
     fn fill_rect(&mut self, rect: &Rect<f32>, pattern: canvas_data::Pattern, draw_options: &DrawOptions) {
@@ -560,13 +1330,24 @@ impl GenericDrawTarget for PixmapTarget {
         draw_options2.shader = pattern.as_tiny_skia().to_owned();
         draw_options2.shader.apply_opacity(self.opacity);
 
+        let tiny_skia_rect = tiny_skia::Rect::from_xywh(
+            rect.origin.x,
+            rect.origin.y,
+            rect.size.width,
+            rect.size.height,
+        ).unwrap();
+        let blend_mode = draw_options2.blend_mode;
+        let anti_alias = draw_options2.anti_alias;
+        let transform = self.transform;
+
+        self.draw_shadow(blend_mode, |scratch| {
+            let mut shadow_paint = tiny_skia::Paint::default();
+            shadow_paint.anti_alias = anti_alias;
+            scratch.fill_rect(tiny_skia_rect, &shadow_paint, transform, None);
+        });
+
         self.pixmap.fill_rect(
-            tiny_skia::Rect::from_xywh(
-                rect.origin.x,
-                rect.origin.y,
-                rect.size.width,
-                rect.size.height,
-            ).unwrap(),
+            tiny_skia_rect,
             &draw_options2,
             self.transform,
             self.mask.as_ref(),
@@ -581,13 +1362,18 @@ impl GenericDrawTarget for PixmapTarget {
         Transform2D::new(self.transform.sx, self.transform.ky, self.transform.kx, self.transform.sy, self.transform.tx, self.transform.ty)
     }
 
+    // `push_clip`/`pop_clip` read `self.antialias`, kept in sync with
+    // `CanvasPaintState::antialias` via `set_antialias` (see the note above
+    // `impl GenericDrawTarget for PixmapTarget`). That mirroring only
+    // compiles once `GenericDrawTarget` itself declares `set_antialias` in
+    // canvas_data.rs, which is the companion edit this request still needs.
     fn pop_clip(&mut self) {
         self.mask_paths.pop();
 
         if !self.mask_paths.is_empty() {
             let mut mask = tiny_skia::Mask::new(self.pixmap.width(), self.pixmap.height()).unwrap();
             for path in &self.mask_paths {
-                mask.fill_path(&path, tiny_skia::FillRule::default(), true, self.transform);
+                mask.fill_path(&path, tiny_skia::FillRule::default(), self.antialias, self.transform);
             }
             self.mask = Some(mask);
         } else {
@@ -601,7 +1387,7 @@ impl GenericDrawTarget for PixmapTarget {
         if !self.mask_paths.is_empty() {
             let mut mask = tiny_skia::Mask::new(self.pixmap.width(), self.pixmap.height()).unwrap();
             for path in &self.mask_paths {
-                mask.fill_path(&path, tiny_skia::FillRule::default(), true, self.transform);
+                mask.fill_path(&path, tiny_skia::FillRule::default(), self.antialias, self.transform);
             }
             self.mask = Some(mask);
         } else {
@@ -639,12 +1425,27 @@ impl GenericDrawTarget for PixmapTarget {
         stroke_options: &StrokeOptions,
         draw_options: &DrawOptions,
     ) {
+        if self.stroke_mode == StrokeMode::AnalyticAa {
+            return self.stroke_analytic_aa(path, pattern, stroke_options);
+        }
+
         // TODO - This pattern is too common; address it
         let mut draw_options = draw_options.clone();
         let mut draw_options2 = draw_options.as_tiny_skia_mut();
         draw_options2.shader = pattern.as_tiny_skia().to_owned();
         draw_options2.shader.apply_opacity(self.opacity);
 
+        let blend_mode = draw_options2.blend_mode;
+        let anti_alias = draw_options2.anti_alias;
+        let transform = self.transform;
+        let tiny_skia_path = path.as_tiny_skia().clone();
+        let stroke = stroke_options.as_tiny_skia().clone();
+        self.draw_shadow(blend_mode, |scratch| {
+            let mut shadow_paint = tiny_skia::Paint::default();
+            shadow_paint.anti_alias = anti_alias;
+            scratch.stroke_path(&tiny_skia_path, &shadow_paint, &stroke, transform, None);
+        });
+
         self.pixmap.stroke_path(path.as_tiny_skia(), &draw_options2, stroke_options.as_tiny_skia(), self.transform, self.mask.as_ref())
     }
 
@@ -720,11 +1521,74 @@ impl GenericDrawTarget for PixmapTarget {
     }
 }
 
-struct PathBuilder(Option<tiny_skia::PathBuilder>);
+/// Default maximum sagitta (chord deviation), in the units `PathBuilder`'s
+/// points are given in, allowed when flattening an arc/ellipse into
+/// quadratic Beziers. See `set_tolerance`.
+const DEFAULT_ARC_TOLERANCE: f32 = 0.1;
+
+/// The largest axis scale factor `transform` applies, i.e. how many device
+/// pixels one unit of path-space maps to along its most-stretched axis. Used
+/// to keep `DEFAULT_ARC_TOLERANCE` meaningful in device pixels regardless of
+/// the active transform — see `PathBuilder::set_tolerance`.
+fn transform_scale(transform: &tiny_skia::Transform) -> f32 {
+    let x_scale = (transform.sx * transform.sx + transform.ky * transform.ky).sqrt();
+    let y_scale = (transform.kx * transform.kx + transform.sy * transform.sy).sqrt();
+    x_scale.max(y_scale).max(f32::EPSILON)
+}
+
+struct PathBuilder(Option<tiny_skia::PathBuilder>, f32);
 
 impl PathBuilder {
     fn new() -> PathBuilder {
-        PathBuilder(Some(tiny_skia::PathBuilder::new()))
+        PathBuilder(Some(tiny_skia::PathBuilder::new()), DEFAULT_ARC_TOLERANCE)
+    }
+
+    /// Sets the maximum sagitta (chord deviation) allowed when flattening
+    /// an arc/ellipse into quadratic Beziers — smaller values tessellate
+    /// more finely. `arc`/`ellipse`/`arc_to` split the arc into sub-arcs
+    /// from this and the arc's own radii, so large/zoomed arcs don't
+    /// under-tessellate. Each sub-arc still goes through lyon_geom's own
+    /// subdivision (at least one quadratic per quarter-pi of sweep), so a
+    /// large tolerance never yields fewer quadratics than lyon_geom's
+    /// default. Callers drawing in a
+    /// scaled-up space should shrink this by the transform's scale to keep
+    /// the tolerance meaningful in device pixels.
+    pub fn set_tolerance(&mut self, tolerance: f32) {
+        self.1 = tolerance.max(1e-3);
+    }
+}
+
+/// Flattens `arc` into quadratic Beziers sized by `tolerance` (the maximum
+/// sagitta a single segment's chord may deviate from the true arc), on top
+/// of lyon_geom's fixed angular subdivision. Works by pre-splitting `arc`
+/// into enough equal sub-arcs that each one's chord error is within
+/// tolerance, then flattening each sub-arc with lyon_geom's
+/// `for_each_quadratic_bezier`.
+fn emit_flattened_arc(arc: &Arc<f32>, tolerance: f32, emit: &mut impl FnMut(&lyon_geom::Point<f32>, &lyon_geom::Point<f32>)) {
+    let max_radius = arc.radii.x.max(arc.radii.y).max(f32::EPSILON);
+    let tolerance = tolerance.min(max_radius);
+
+    // Sagitta bound: a chord spanning angle `theta` across a circle of
+    // radius `r` deviates from the arc by `r * (1 - cos(theta / 2))`.
+    let max_angle = 2.0 * (1.0 - tolerance / max_radius).acos();
+    let max_angle = if max_angle.is_finite() && max_angle > 0.01 {
+        max_angle
+    } else {
+        std::f32::consts::FRAC_PI_2
+    };
+
+    let segment_count = (arc.sweep_angle.radians.abs() / max_angle).ceil().max(1.0) as u32;
+    let step = arc.sweep_angle.radians / segment_count as f32;
+
+    for i in 0..segment_count {
+        let sub_arc = Arc {
+            center: arc.center,
+            radii: arc.radii,
+            start_angle: Angle::radians(arc.start_angle.radians + step * i as f32),
+            sweep_angle: Angle::radians(step),
+            x_rotation: arc.x_rotation,
+        };
+        sub_arc.for_each_quadratic_bezier(&mut |q| emit(&q.ctrl, &q.to));
     }
 }
 
@@ -821,8 +1685,9 @@ impl GenericPathBuilder for PathBuilder {
 
         self.line_to(arc.from());
 
-        arc.for_each_quadratic_bezier(&mut |q| {
-            self.quadratic_curve_to(&q.ctrl, &q.to);
+        let tolerance = self.1;
+        emit_flattened_arc(&arc, tolerance, &mut |ctrl, to| {
+            self.quadratic_curve_to(ctrl, to);
         });
     }
 
@@ -852,6 +1717,97 @@ impl GenericPathBuilder for PathBuilder {
     }
 }
 
+impl PathBuilder {
+    /// Adds an SVG/Canvas2D elliptical arc given in endpoint notation — the
+    /// current point as the start, `end` as the endpoint, `radii`,
+    /// `x_axis_rotation`, and the `large_arc`/`sweep` flags — by converting
+    /// to lyon_geom's center parameterization and flattening it the same
+    /// way `ellipse` does. See the SVG 1.1 spec, appendix F.6.5.
+    pub fn arc_to(
+        &mut self,
+        end: Point2D<f32>,
+        mut radii: Vector2D<f32>,
+        x_axis_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+    ) {
+        let Some(start) = self.get_current_point() else {
+            self.move_to(end);
+            return;
+        };
+
+        if radii.x == 0. || radii.y == 0. {
+            self.line_to(end);
+            return;
+        }
+
+        radii.x = radii.x.abs();
+        radii.y = radii.y.abs();
+
+        let phi = Angle::radians(x_axis_rotation);
+        let (sin_phi, cos_phi) = (phi.radians.sin(), phi.radians.cos());
+
+        // (x1', y1') = R(-phi) . (p0 - p1) / 2
+        let half_delta = Vector2D::new((start.x - end.x) / 2., (start.y - end.y) / 2.);
+        let x1p = cos_phi * half_delta.x + sin_phi * half_delta.y;
+        let y1p = -sin_phi * half_delta.x + cos_phi * half_delta.y;
+
+        // Scale up radii that are too small to reach the endpoint.
+        let lambda = (x1p * x1p) / (radii.x * radii.x) + (y1p * y1p) / (radii.y * radii.y);
+        if lambda > 1. {
+            let scale = lambda.sqrt();
+            radii.x *= scale;
+            radii.y *= scale;
+        }
+
+        let rx2 = radii.x * radii.x;
+        let ry2 = radii.y * radii.y;
+        let numerator = (rx2 * ry2 - rx2 * y1p * y1p - ry2 * x1p * x1p).max(0.);
+        let denominator = rx2 * y1p * y1p + ry2 * x1p * x1p;
+        let co = if denominator == 0. { 0. } else { (numerator / denominator).sqrt() };
+        let co = if large_arc == sweep { -co } else { co };
+
+        let cxp = co * (radii.x * y1p / radii.y);
+        let cyp = co * -(radii.y * x1p / radii.x);
+
+        let center = Point2D::new(
+            cos_phi * cxp - sin_phi * cyp + (start.x + end.x) / 2.,
+            sin_phi * cxp + cos_phi * cyp + (start.y + end.y) / 2.,
+        );
+
+        let angle_between = |u: Vector2D<f32>, v: Vector2D<f32>| -> Angle<f32> {
+            let sign = if u.x * v.y - u.y * v.x < 0. { -1. } else { 1. };
+            let cos_angle = (u.x * v.x + u.y * v.y) / (u.length() * v.length());
+            Angle::radians(sign * cos_angle.clamp(-1., 1.).acos())
+        };
+
+        let start_vector = Vector2D::new((x1p - cxp) / radii.x, (y1p - cyp) / radii.y);
+        let end_vector = Vector2D::new((-x1p - cxp) / radii.x, (-y1p - cyp) / radii.y);
+
+        let start_angle = angle_between(Vector2D::new(1., 0.), start_vector);
+        let mut sweep_angle = angle_between(start_vector, end_vector);
+
+        if !sweep && sweep_angle.radians > 0. {
+            sweep_angle = sweep_angle - Angle::two_pi();
+        } else if sweep && sweep_angle.radians < 0. {
+            sweep_angle = sweep_angle + Angle::two_pi();
+        }
+
+        let arc: Arc<f32> = Arc {
+            center,
+            radii,
+            start_angle,
+            sweep_angle,
+            x_rotation: phi,
+        };
+
+        let tolerance = self.1;
+        emit_flattened_arc(&arc, tolerance, &mut |ctrl, to| {
+            self.quadratic_curve_to(ctrl, to);
+        });
+    }
+}
+
 impl Filter {
     fn as_tiny_skia(&self) -> tiny_skia::FilterQuality {
         match self {
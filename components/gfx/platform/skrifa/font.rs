@@ -2,13 +2,16 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use std::collections::HashMap;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 
 use app_units::Au;
 use skrifa::attribute::Attributes;
 use skrifa::font::FontRef;
-use skrifa::instance::{LocationRef, Size};
+use skrifa::instance::{Location, LocationRef, Size};
+use skrifa::raw::TableProvider;
+use skrifa::raw::tables::gpos::{PairPos, PositionLookup};
 use skrifa::string::StringId;
 use skrifa::{MetadataProvider, Tag};
 use style::values::computed::font::{FontStretch, FontStyle, FontWeight};
@@ -29,13 +32,224 @@ impl FontTableMethods for FontTable {
     }
 }
 
-// I hate lifetimes
+/// How an extracted glyph outline should be hinted before rasterization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HintingMode {
+    None,
+    Light,
+    Full,
+}
+
+/// Which coverage representation a rasterized glyph should produce,
+/// mirroring the modes the WebRender FreeType backend exposes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    /// 1-bit coverage: a pixel is either fully covered or not.
+    Mono,
+    /// 8-bit grayscale antialiased coverage.
+    Alpha,
+    /// Three 8-bit coverage planes, one per LCD subpixel component.
+    Subpixel,
+}
+
+/// The position and extents of a rasterized glyph bitmap relative to the
+/// glyph's origin, plus its horizontal advance.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GlyphDimensions {
+    pub left: i32,
+    pub top: i32,
+    pub width: u32,
+    pub height: u32,
+    pub advance: FractionalPixel,
+}
+
+/// A rasterized glyph: its dimensions plus a row-major coverage bitmap (one
+/// byte per pixel for `Mono`/`Alpha`, three bytes (R, G, B) per pixel for
+/// `Subpixel`).
 #[derive(Debug)]
-pub struct Test(Vec<u8>);
+pub struct RasterizedGlyph {
+    pub dimensions: GlyphDimensions,
+    pub bitmap: Vec<u8>,
+}
+
+/// Collects a glyph outline as a set of closed, line-flattened contours so
+/// it can be scan-converted without depending on a rasterization crate.
+#[derive(Default)]
+struct ContourCollector {
+    contours: Vec<Vec<(f32, f32)>>,
+    current: Vec<(f32, f32)>,
+}
+
+impl ContourCollector {
+    fn finish_current(&mut self) {
+        if self.current.len() > 1 {
+            self.contours.push(std::mem::take(&mut self.current));
+        } else {
+            self.current.clear();
+        }
+    }
+}
+
+impl skrifa::outline::OutlinePen for ContourCollector {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.finish_current();
+        self.current.push((x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.current.push((x, y));
+    }
+
+    fn quad_to(&mut self, cx0: f32, cy0: f32, x: f32, y: f32) {
+        let Some(&(x0, y0)) = self.current.last() else {
+            return;
+        };
+        const STEPS: usize = 8;
+        for i in 1..=STEPS {
+            let t = i as f32 / STEPS as f32;
+            let mt = 1.0 - t;
+            self.current.push((
+                mt * mt * x0 + 2.0 * mt * t * cx0 + t * t * x,
+                mt * mt * y0 + 2.0 * mt * t * cy0 + t * t * y,
+            ));
+        }
+    }
+
+    fn curve_to(&mut self, cx0: f32, cy0: f32, cx1: f32, cy1: f32, x: f32, y: f32) {
+        let Some(&(x0, y0)) = self.current.last() else {
+            return;
+        };
+        const STEPS: usize = 12;
+        for i in 1..=STEPS {
+            let t = i as f32 / STEPS as f32;
+            let mt = 1.0 - t;
+            self.current.push((
+                mt.powi(3) * x0 + 3.0 * mt * mt * t * cx0 + 3.0 * mt * t * t * cx1 + t.powi(3) * x,
+                mt.powi(3) * y0 + 3.0 * mt * mt * t * cy0 + 3.0 * mt * t * t * cy1 + t.powi(3) * y,
+            ));
+        }
+    }
 
-impl Test {
-    pub fn get_font_ref(&self) -> FontRef {
-        FontRef::new(&self.0).unwrap()
+    fn close(&mut self) {
+        self.finish_current();
+    }
+}
+
+/// Nonzero-winding point-in-polygon test, used by [`rasterize_contours`] to
+/// supersample coverage.
+fn contours_contain(contours: &[Vec<(f32, f32)>], x: f32, y: f32) -> bool {
+    let mut winding = 0i32;
+    for contour in contours {
+        let n = contour.len();
+        for i in 0..n {
+            let (x0, y0) = contour[i];
+            let (x1, y1) = contour[(i + 1) % n];
+            if (y0 <= y) != (y1 <= y) {
+                let t = (y - y0) / (y1 - y0);
+                if x0 + t * (x1 - x0) > x {
+                    winding += if y1 > y0 { 1 } else { -1 };
+                }
+            }
+        }
+    }
+    winding != 0
+}
+
+/// Scan-converts flattened glyph contours into a coverage bitmap, using 4x4
+/// supersampling per pixel (and, for `Subpixel`, one horizontal sample
+/// offset per LCD component, three in all).
+fn rasterize_contours(
+    contours: &[Vec<(f32, f32)>],
+    render_mode: RenderMode,
+) -> (GlyphDimensions, Vec<u8>) {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for &(x, y) in contours.iter().flatten() {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    if !min_x.is_finite() {
+        return (GlyphDimensions::default(), Vec::new());
+    }
+
+    let left = min_x.floor() as i32;
+    let top = max_y.ceil() as i32;
+    let width = (max_x.ceil() - min_x.floor()).max(1.0) as u32;
+    let height = (max_y.ceil() - min_y.floor()).max(1.0) as u32;
+
+    const SUPERSAMPLE: usize = 4;
+    let channels = if render_mode == RenderMode::Subpixel { 3 } else { 1 };
+    let subpixel_offsets: &[f32] = if channels == 3 {
+        &[-1.0 / 3.0, 0.0, 1.0 / 3.0]
+    } else {
+        &[0.0]
+    };
+    let mut bitmap = vec![0u8; width as usize * height as usize * channels];
+
+    for row in 0..height {
+        for col in 0..width {
+            let idx = (row as usize * width as usize + col as usize) * channels;
+            for (channel, &dx) in subpixel_offsets.iter().enumerate() {
+                let mut hits = 0u32;
+                for sy in 0..SUPERSAMPLE {
+                    for sx in 0..SUPERSAMPLE {
+                        let x = left as f32 + col as f32 + dx + (sx as f32 + 0.5) / SUPERSAMPLE as f32;
+                        let y = top as f32 - row as f32 - (sy as f32 + 0.5) / SUPERSAMPLE as f32;
+                        if contours_contain(contours, x, y) {
+                            hits += 1;
+                        }
+                    }
+                }
+                let total = (SUPERSAMPLE * SUPERSAMPLE) as u32;
+                bitmap[idx + channel] = if channels == 1 && render_mode == RenderMode::Mono {
+                    if hits * 2 >= total { 255 } else { 0 }
+                } else {
+                    (hits * 255 / total) as u8
+                };
+            }
+        }
+    }
+
+    (
+        GlyphDimensions {
+            left,
+            top,
+            width,
+            height,
+            advance: 0.0,
+        },
+        bitmap,
+    )
+}
+
+/// A parsed [`FontRef`] together with the byte buffer it borrows from.
+///
+/// `skrifa::font::FontRef` borrows from the raw font bytes, so in order to
+/// parse the sfnt header and table directory once and reuse it across every
+/// glyph/metric call, we need a small self-referential wrapper: the bytes are
+/// heap-allocated behind an `Arc` and never moved or mutated for as long as
+/// the `CachedFont` (or a clone of its `Arc`) is alive, so it's sound to
+/// extend the borrow to `'static` and store it alongside its owner.
+#[derive(Debug)]
+struct CachedFont {
+    #[allow(dead_code)]
+    data: Arc<Vec<u8>>,
+    font_ref: FontRef<'static>,
+}
+
+impl CachedFont {
+    fn new(data: Arc<Vec<u8>>) -> Self {
+        // SAFETY: `font_ref` borrows from `data`. `data` is heap-allocated
+        // and reference-counted, and `CachedFont` keeps a clone of that `Arc`
+        // alive for as long as `font_ref` is reachable, so the bytes it
+        // points into never move or get freed while this borrow is in use.
+        let font_ref: FontRef<'static> = unsafe { std::mem::transmute(FontRef::new(&data).unwrap()) };
+        Self { data, font_ref }
+    }
+
+    fn get_font_ref(&self) -> &FontRef<'static> {
+        &self.font_ref
     }
 }
 
@@ -46,37 +260,504 @@ pub struct FontInfo {
     attributes: Attributes,
 }
 
+/// Pair-kerning data extracted from either the `GPOS` table's pair-adjustment
+/// lookups or the legacy `kern` table, whichever the font provides. Values
+/// are in font design units and still need scaling by `ppem / units_per_em`.
+#[derive(Debug)]
+enum KerningData {
+    Gpos(HashMap<(u16, u16), i32>),
+    Kern(HashMap<(u16, u16), i32>),
+    None,
+}
+
 #[derive(Debug)]
 pub struct FontHandle {
     font_data: Arc<FontTemplateData>,
-    test: Test,
+    font: CachedFont,
     info: FontInfo,
     em_size: Size,
+    /// The variation-axis coordinates (`wght`, `wdth`, `slnt`, `opsz`, and
+    /// any custom axes) this handle should use when querying advances,
+    /// metrics, and outlines. Defaults to the font's default instance.
+    location: Location,
+    /// Parsed lazily on first use of [`FontHandleMethods::glyph_h_kerning`]
+    /// and cached for the lifetime of the handle.
+    kerning: OnceLock<KerningData>,
+    /// Built lazily on first use of [`FontHandle::rasterize_glyph`] with
+    /// [`HintingMode::Light`], and cached since it only depends on `em_size`
+    /// (fixed once a `FontHandle` exists) and `location` (changing it via
+    /// [`FontHandle::set_variations`] discards the cache). `None` once
+    /// resolved if the face has no hinting program to run.
+    light_hinting_instance: OnceLock<Option<skrifa::outline::HintingInstance>>,
+    /// Same as `light_hinting_instance`, but for [`HintingMode::Full`].
+    full_hinting_instance: OnceLock<Option<skrifa::outline::HintingInstance>>,
 }
 
-impl FontHandleMethods for FontHandle {
-    fn new_from_template(
+impl FontHandle {
+    /// The variation axes this font exposes, as `(tag, min, default, max)`
+    /// user-space tuples, so the font-matching layer can resolve CSS
+    /// `font-variation-settings` against axes the face actually supports.
+    pub fn variation_axes(&self) -> Vec<(Tag, f32, f32, f32)> {
+        self.font
+            .get_font_ref()
+            .axes()
+            .iter()
+            .map(|axis| {
+                (
+                    axis.tag(),
+                    axis.min_value(),
+                    axis.default_value(),
+                    axis.max_value(),
+                )
+            })
+            .collect()
+    }
+
+    /// The font's named instances (e.g. "Condensed Bold"), as a display
+    /// name paired with the axis coordinates it resolves to.
+    pub fn named_instances(&self) -> Vec<(Option<String>, Vec<(Tag, f32)>)> {
+        let font_ref = self.font.get_font_ref();
+        let tags: Vec<Tag> = font_ref.axes().iter().map(|axis| axis.tag()).collect();
+        font_ref
+            .named_instances()
+            .iter()
+            .map(|instance| {
+                let name = font_ref
+                    .localized_strings(instance.subfamily_name_id())
+                    .english_or_first()
+                    .map(|locstr| locstr.to_string());
+                let coords = tags
+                    .iter()
+                    .copied()
+                    .zip(instance.user_coords())
+                    .collect();
+                (name, coords)
+            })
+            .collect()
+    }
+
+    /// Sets the variation-axis coordinates (resolved from CSS
+    /// `font-variation-settings` together with the computed `font-weight`,
+    /// `font-stretch`, and `font-style`) that subsequent glyph and metric
+    /// queries should use.
+    pub fn set_variations(&mut self, user_coords: &[(Tag, f32)]) {
+        self.location = self
+            .font
+            .get_font_ref()
+            .axes()
+            .location(user_coords.iter().copied());
+        // The cached hinting instances were built for the previous location.
+        self.light_hinting_instance = OnceLock::new();
+        self.full_hinting_instance = OnceLock::new();
+    }
+
+    fn location_ref(&self) -> LocationRef {
+        (&self.location).into()
+    }
+
+    fn kerning_data(&self) -> &KerningData {
+        self.kerning.get_or_init(|| {
+            let font_ref = self.font.get_font_ref();
+            Self::read_gpos_pairs(font_ref)
+                .map(KerningData::Gpos)
+                .or_else(|| Self::read_kern_pairs(font_ref).map(KerningData::Kern))
+                .unwrap_or(KerningData::None)
+        })
+    }
+
+    /// Reads the simple (non-contextual) pair-adjustment lookups out of
+    /// `GPOS`, keyed by `(left_glyph, right_glyph)` with the x-advance
+    /// adjustment in font design units. Class-based (format 2) pair
+    /// positioning is skipped in favour of falling back to the `kern` table,
+    /// since it requires a full class-definition lookup this caller doesn't
+    /// need for plain kerning.
+    fn read_gpos_pairs(font_ref: &FontRef) -> Option<HashMap<(u16, u16), i32>> {
+        let gpos = font_ref.gpos().ok()?;
+        let lookup_list = gpos.lookup_list().ok()?;
+        let mut pairs = HashMap::new();
+        for lookup in lookup_list.lookups().iter().flatten() {
+            let PositionLookup::Pair(pair_lookup) = lookup else {
+                continue;
+            };
+            for subtable in pair_lookup.subtables().iter().flatten() {
+                let PairPos::Format1(format1) = subtable else {
+                    continue;
+                };
+                let Ok(coverage) = format1.coverage() else {
+                    continue;
+                };
+                for (left_glyph, pair_set) in coverage.iter().zip(format1.pair_sets().iter()) {
+                    let Ok(pair_set) = pair_set else {
+                        continue;
+                    };
+                    for record in pair_set.pair_value_records().iter().flatten() {
+                        let x_advance = record.value_record1.x_advance().unwrap_or_default();
+                        if x_advance != 0 {
+                            pairs.insert((left_glyph.to_u16(), record.second_glyph.get().to_u16()), x_advance as i32);
+                        }
+                    }
+                }
+            }
+        }
+        (!pairs.is_empty()).then_some(pairs)
+    }
+
+    /// Reads the classic `kern` table's format-0 subtable: a sorted list of
+    /// `(left_glyph, right_glyph) -> FWord` pairs, keyed the same way as
+    /// [`Self::read_gpos_pairs`].
+    fn read_kern_pairs(font_ref: &FontRef) -> Option<HashMap<(u16, u16), i32>> {
+        let data = font_ref.table_data(Tag::new(b"kern"))?;
+        let bytes = data.as_bytes();
+        if bytes.len() < 4 {
+            return None;
+        }
+        let n_tables = u16::from_be_bytes([bytes[2], bytes[3]]);
+        let mut offset = 4usize;
+        for _ in 0..n_tables {
+            let header = bytes.get(offset..offset + 6)?;
+            let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+            let format = header[4];
+            if format == 0 {
+                let subtable = bytes.get(offset + 6..offset + length)?;
+                let n_pairs = u16::from_be_bytes([*subtable.get(0)?, *subtable.get(1)?]) as usize;
+                let mut pairs = HashMap::with_capacity(n_pairs);
+                for i in 0..n_pairs {
+                    let pair = subtable.get(8 + i * 6..8 + i * 6 + 6)?;
+                    let left = u16::from_be_bytes([pair[0], pair[1]]);
+                    let right = u16::from_be_bytes([pair[2], pair[3]]);
+                    let value = i16::from_be_bytes([pair[4], pair[5]]);
+                    pairs.insert((left, right), value as i32);
+                }
+                return Some(pairs);
+            }
+            offset += length.max(6);
+        }
+        None
+    }
+
+    /// Builds (or returns the cached) [`skrifa::outline::HintingInstance`]
+    /// for `mode`, run against this handle's `em_size`/`location`. Returns
+    /// `None` if the face has no hinting instructions to run, in which case
+    /// callers should fall back to an unhinted outline.
+    fn hinting_instance(&self, mode: HintingMode) -> Option<&skrifa::outline::HintingInstance> {
+        let cache = match mode {
+            HintingMode::None => return None,
+            HintingMode::Light => &self.light_hinting_instance,
+            HintingMode::Full => &self.full_hinting_instance,
+        };
+        cache
+            .get_or_init(|| {
+                let font_ref = self.font.get_font_ref();
+                let skrifa_mode = match mode {
+                    HintingMode::None => return None,
+                    HintingMode::Light => skrifa::outline::HintingMode::Smooth {
+                        lcd_subpixel: None,
+                        preserve_linear_metrics: true,
+                    },
+                    HintingMode::Full => skrifa::outline::HintingMode::Strong,
+                };
+                skrifa::outline::HintingInstance::new(
+                    &font_ref.outline_glyphs(),
+                    self.em_size,
+                    self.location_ref(),
+                    skrifa_mode,
+                )
+                .ok()
+            })
+            .as_ref()
+    }
+
+    fn units_per_em(&self) -> f64 {
+        self.font
+            .get_font_ref()
+            .head()
+            .map(|head| head.units_per_em() as f64)
+            .unwrap_or(1000.0)
+    }
+
+    /// Rasterizes `glyph` at this handle's `em_size`/location into a
+    /// coverage bitmap, honoring the requested render mode and hinting.
+    pub fn rasterize_glyph(
+        &self,
+        glyph: GlyphId,
+        render_mode: RenderMode,
+        hinting: HintingMode,
+    ) -> Option<RasterizedGlyph> {
+        let font_ref = self.font.get_font_ref();
+        let outline = font_ref
+            .outline_glyphs()
+            .get(skrifa::GlyphId::new(glyph as u16))?;
+
+        let settings = match self.hinting_instance(hinting) {
+            Some(instance) => skrifa::outline::DrawSettings::hinted(instance, false),
+            None => skrifa::outline::DrawSettings::unhinted(self.em_size, self.location_ref()),
+        };
+
+        let mut pen = ContourCollector::default();
+        outline.draw(settings, &mut pen).ok()?;
+        pen.finish_current();
+
+        let (mut dimensions, bitmap) = rasterize_contours(&pen.contours, render_mode);
+        dimensions.advance = self.glyph_h_advance(glyph).unwrap_or(0.0);
+
+        Some(RasterizedGlyph { dimensions, bitmap })
+    }
+
+    /// Whether `glyph` has an embedded bitmap strike (`CBDT`/`CBLC`, `sbix`)
+    /// or a `COLR`/`CPAL` color representation, so callers can pick the RGBA
+    /// blend path instead of the grayscale-coverage one.
+    pub fn is_colored_glyph(&self, glyph: GlyphId) -> bool {
+        let font_ref = self.font.get_font_ref();
+        let skrifa_glyph = skrifa::GlyphId::new(glyph as u16);
+        font_ref
+            .bitmap_strikes()
+            .glyph_for_size(self.em_size, skrifa_glyph)
+            .is_some() ||
+            font_ref.color_glyphs().get(skrifa_glyph).is_some()
+    }
+
+    /// Rasterizes an embedded bitmap or `COLR`/`CPAL` color glyph into a
+    /// premultiplied RGBA bitmap. Returns `None` when `glyph` has neither
+    /// representation; callers should fall back to [`Self::rasterize_glyph`]
+    /// for plain outline glyphs.
+    pub fn rasterize_color_glyph(&self, glyph: GlyphId) -> Option<RasterizedGlyph> {
+        let font_ref = self.font.get_font_ref();
+        let skrifa_glyph = skrifa::GlyphId::new(glyph as u16);
+
+        if let Some(strike) = font_ref.bitmap_strikes().glyph_for_size(self.em_size, skrifa_glyph) {
+            return self.rasterize_bitmap_strike(glyph, strike);
+        }
+
+        let color_glyph = font_ref.color_glyphs().get(skrifa_glyph)?;
+        let mut painter = ColrLayerCollector {
+            font_ref: &font_ref,
+            em_size: self.em_size,
+            location: self.location_ref(),
+            pending_contours: Vec::new(),
+            layers: Vec::new(),
+        };
+        color_glyph.paint(self.location_ref(), &mut painter).ok()?;
+
+        let (mut dimensions, bitmap) = composite_colr_layers(&painter.layers);
+        dimensions.advance = self.glyph_h_advance(glyph).unwrap_or(0.0);
+        Some(RasterizedGlyph { dimensions, bitmap })
+    }
+
+    /// Decodes an embedded `CBDT`/`sbix` bitmap strike into premultiplied
+    /// RGBA. Strike data is stored as a compressed image (typically PNG),
+    /// which we hand off to the `image` crate already used elsewhere in the
+    /// layout pipeline for raster image decoding.
+    fn rasterize_bitmap_strike(
+        &self,
+        glyph: GlyphId,
+        strike: skrifa::bitmap::BitmapGlyph,
+    ) -> Option<RasterizedGlyph> {
+        let image = image::load_from_memory(strike.data).ok()?.into_rgba8();
+        let (width, height) = image.dimensions();
+        Some(RasterizedGlyph {
+            dimensions: GlyphDimensions {
+                left: strike.bearing_x.round() as i32,
+                top: strike.bearing_y.round() as i32,
+                width,
+                height,
+                advance: self.glyph_h_advance(glyph).unwrap_or(0.0),
+            },
+            bitmap: premultiply_rgba(image.into_raw()),
+        })
+    }
+}
+
+/// One resolved `COLR` paint layer: the glyph outline it fills and the
+/// premultiplied RGBA color (from `CPAL`, or black for the special
+/// "current text color" foreground index) it's filled with.
+struct ColrLayer {
+    contours: Vec<Vec<(f32, f32)>>,
+    color: [u8; 4],
+}
+
+/// Walks a `COLRv1` paint graph, capturing each `push_clip_glyph` + `fill`
+/// pair as a flattened outline plus a resolved solid color. Gradient
+/// brushes (`PaintLinearGradient`, `PaintRadialGradient`, `PaintSweepGradient`)
+/// are approximated by their first color stop rather than fully evaluated;
+/// that's a reasonable approximation for the common case of a simple
+/// (COLRv0-style) layered emoji/icon font, and a real gradient evaluation can
+/// be layered on top later the same way the tiny-skia canvas backend's
+/// gradient shaders were.
+struct ColrLayerCollector<'a> {
+    font_ref: &'a FontRef<'a>,
+    em_size: Size,
+    location: LocationRef<'a>,
+    pending_contours: Vec<Vec<(f32, f32)>>,
+    layers: Vec<ColrLayer>,
+}
+
+impl<'a> ColrLayerCollector<'a> {
+    /// The special `CPAL` palette index meaning "use the current text
+    /// color" rather than a palette entry.
+    const FOREGROUND_COLOR_INDEX: u16 = 0xffff;
+
+    fn resolve_color(&self, palette_index: u16, alpha: f32) -> [u8; 4] {
+        if palette_index == Self::FOREGROUND_COLOR_INDEX {
+            return [0, 0, 0, (alpha * 255.0).round() as u8];
+        }
+        let Ok(cpal) = self.font_ref.cpal() else {
+            return [0, 0, 0, 255];
+        };
+        let Ok(records) = cpal.color_records_array().transpose() else {
+            return [0, 0, 0, 255];
+        };
+        let Some(record) = records.get(palette_index as usize) else {
+            return [0, 0, 0, 255];
+        };
+        let a = (record.alpha as f32 * alpha).round() as u8;
+        let premultiply = |c: u8| ((c as u16 * a as u16) / 255) as u8;
+        [
+            premultiply(record.red),
+            premultiply(record.green),
+            premultiply(record.blue),
+            a,
+        ]
+    }
+}
+
+impl<'a> skrifa::color::ColorPainter for ColrLayerCollector<'a> {
+    fn push_clip_glyph(&mut self, glyph_id: skrifa::GlyphId) {
+        let mut pen = ContourCollector::default();
+        if let Some(outline) = self.font_ref.outline_glyphs().get(glyph_id) {
+            let settings = skrifa::outline::DrawSettings::unhinted(self.em_size, self.location);
+            let _ = outline.draw(settings, &mut pen);
+        }
+        pen.finish_current();
+        self.pending_contours = pen.contours;
+    }
+
+    fn push_clip_box(&mut self, _clip_box: skrifa::color::ClipBox) {}
+    fn pop_clip(&mut self) {}
+    fn push_layer(&mut self, _mode: skrifa::color::CompositeMode) {}
+    fn pop_layer(&mut self) {}
+    fn push_transform(&mut self, _transform: skrifa::color::Transform) {}
+    fn pop_transform(&mut self) {}
+
+    fn fill(&mut self, brush: skrifa::color::Brush<'_>) {
+        let color = match brush {
+            skrifa::color::Brush::Solid { palette_index, alpha, .. } => {
+                self.resolve_color(palette_index, alpha)
+            },
+            skrifa::color::Brush::LinearGradient { color_stops, .. } |
+            skrifa::color::Brush::RadialGradient { color_stops, .. } |
+            skrifa::color::Brush::SweepGradient { color_stops, .. } => color_stops
+                .first()
+                .map(|stop| self.resolve_color(stop.palette_index, stop.alpha))
+                .unwrap_or([0, 0, 0, 255]),
+        };
+        self.layers.push(ColrLayer {
+            contours: std::mem::take(&mut self.pending_contours),
+            color,
+        });
+    }
+}
+
+/// Premultiplies a straight-alpha RGBA buffer (as decoded by `image`) in
+/// place.
+fn premultiply_rgba(mut rgba: Vec<u8>) -> Vec<u8> {
+    for pixel in rgba.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u16;
+        pixel[0] = ((pixel[0] as u16 * alpha) / 255) as u8;
+        pixel[1] = ((pixel[1] as u16 * alpha) / 255) as u8;
+        pixel[2] = ((pixel[2] as u16 * alpha) / 255) as u8;
+    }
+    rgba
+}
+
+/// Composites a `COLR` glyph's resolved layers, back to front, into a single
+/// premultiplied RGBA bitmap sized to their combined bounding box.
+fn composite_colr_layers(layers: &[ColrLayer]) -> (GlyphDimensions, Vec<u8>) {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for &(x, y) in layers.iter().flat_map(|layer| layer.contours.iter()).flatten() {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    if !min_x.is_finite() {
+        return (GlyphDimensions::default(), Vec::new());
+    }
+
+    let left = min_x.floor() as i32;
+    let top = max_y.ceil() as i32;
+    let width = (max_x.ceil() - min_x.floor()).max(1.0) as u32;
+    let height = (max_y.ceil() - min_y.floor()).max(1.0) as u32;
+    let mut rgba = vec![0u8; width as usize * height as usize * 4];
+
+    for layer in layers {
+        let (layer_dimensions, alpha) = rasterize_contours(&layer.contours, RenderMode::Alpha);
+        if alpha.is_empty() {
+            continue;
+        }
+        let [r, g, b, a] = layer.color;
+        // Each layer's bitmap covers only its own bounding box; place it at
+        // its offset inside the combined one.
+        let offset_x = (layer_dimensions.left - left) as usize;
+        let offset_y = (top - layer_dimensions.top) as usize;
+        for (row, coverage_row) in alpha.chunks_exact(layer_dimensions.width as usize).enumerate() {
+            let row_len = coverage_row.len().min((width as usize).saturating_sub(offset_x));
+            let start = ((offset_y + row) * width as usize + offset_x) * 4;
+            let Some(dst_row) = rgba.get_mut(start..start + row_len * 4) else {
+                continue;
+            };
+            for (pixel, &coverage) in dst_row.chunks_exact_mut(4).zip(coverage_row.iter()) {
+                let src_a = (a as u32 * coverage as u32) / 255;
+                let inv_src_a = 255 - src_a;
+                pixel[0] = (((r as u32 * coverage as u32) / 255 + pixel[0] as u32 * inv_src_a / 255) as u8).min(255);
+                pixel[1] = (((g as u32 * coverage as u32) / 255 + pixel[1] as u32 * inv_src_a / 255) as u8).min(255);
+                pixel[2] = (((b as u32 * coverage as u32) / 255 + pixel[2] as u32 * inv_src_a / 255) as u8).min(255);
+                pixel[3] = (src_a + (pixel[3] as u32 * inv_src_a) / 255).min(255) as u8;
+            }
+        }
+    }
+
+    (
+        GlyphDimensions {
+            left,
+            top,
+            width,
+            height,
+            advance: 0.0,
+        },
+        rgba,
+    )
+}
+
+impl FontHandle {
+    /// Builds a handle from a raw `f32` pixel size rather than the `Au`
+    /// (1/60px fixed-point) size `new_from_template` takes. `Au` quantizes
+    /// at small sizes and for variable `opsz`/subpixel positioning, so
+    /// callers that can supply an exact size should use this instead; `Au`
+    /// conversion only happens at the very edges that still require it
+    /// (constructing a default size, and [`FontHandleMethods::metrics`]'s
+    /// `FontMetrics` return type).
+    pub fn new_from_template_with_px_size(
         _fctx: &FontContextHandle,
         template: Arc<FontTemplateData>,
-        pt_size: Option<app_units::Au>,
+        px_size: Option<f32>,
     ) -> Result<Self, ()> {
-        let test = if let Some(ref bytes) = template.bytes {
-            Test(bytes.to_owned())
+        let bytes = if let Some(ref bytes) = template.bytes {
+            bytes.to_owned()
         } else {
-            let bytes = std::fs::read(Path::new(template.identifier.as_ref())).unwrap();
-            Test(bytes)
+            std::fs::read(Path::new(template.identifier.as_ref())).unwrap()
         };
-        let font = test.get_font_ref();
+        let font = CachedFont::new(Arc::new(bytes));
 
-        let family_name = font
+        let font_ref = font.get_font_ref();
+        let family_name = font_ref
             .localized_strings(StringId::FAMILY_NAME)
             .english_or_first()
             .map(|locstr| locstr.to_string());
-        let subfamily_name = font
+        let subfamily_name = font_ref
             .localized_strings(StringId::SUBFAMILY_NAME)
             .english_or_first()
             .map(|locstr| locstr.to_string());
-        let attributes = font.attributes();
+        let attributes = font_ref.attributes();
 
         let info = FontInfo {
             family_name,
@@ -84,17 +765,37 @@ impl FontHandleMethods for FontHandle {
             attributes,
         };
 
-        let pt_size = pt_size.unwrap_or(Au::from_f32_px(16.0));
-        let em_size = Size::new(pt_size.to_f32_px());
+        let em_size = Size::new(px_size.unwrap_or(16.0));
 
         Ok(Self {
             font_data: template,
-            test,
+            font,
             info,
             em_size,
+            location: Location::default(),
+            kerning: OnceLock::new(),
+            light_hinting_instance: OnceLock::new(),
+            full_hinting_instance: OnceLock::new(),
         })
     }
 
+    /// This handle's font size in raw pixels, without the `Au` rounding
+    /// `metrics()`'s `FontMetrics::em_size` goes through.
+    pub fn px_size(&self) -> f32 {
+        self.em_size.ppem().unwrap_or(0.0)
+    }
+}
+
+impl FontHandleMethods for FontHandle {
+    fn new_from_template(
+        fctx: &FontContextHandle,
+        template: Arc<FontTemplateData>,
+        pt_size: Option<app_units::Au>,
+    ) -> Result<Self, ()> {
+        let px_size = Some(pt_size.unwrap_or(Au::from_f32_px(16.0)).to_f32_px());
+        Self::new_from_template_with_px_size(fctx, template, px_size)
+    }
+
     fn template(&self) -> Arc<FontTemplateData> {
         self.font_data.clone()
     }
@@ -126,7 +827,7 @@ impl FontHandleMethods for FontHandle {
     }
 
     fn glyph_index(&self, codepoint: char) -> Option<GlyphId> {
-        self.test
+        self.font
             .get_font_ref()
             .charmap()
             .map(codepoint)
@@ -134,26 +835,44 @@ impl FontHandleMethods for FontHandle {
     }
 
     fn glyph_h_advance(&self, glyph: GlyphId) -> Option<FractionalPixel> {
-        self.test
+        self.font
             .get_font_ref()
-            .glyph_metrics(self.em_size, LocationRef::default())
+            .glyph_metrics(self.em_size, self.location_ref())
             .advance_width(skrifa::GlyphId::new(glyph as u16))
             .map(|adv| adv as f64)
     }
 
-    fn glyph_h_kerning(&self, _: GlyphId, _: GlyphId) -> FractionalPixel {
-        0.0
+    fn glyph_h_kerning(&self, first_glyph: GlyphId, second_glyph: GlyphId) -> FractionalPixel {
+        let key = (first_glyph as u16, second_glyph as u16);
+        let pairs = match self.kerning_data() {
+            KerningData::Gpos(pairs) | KerningData::Kern(pairs) => pairs,
+            KerningData::None => return 0.0,
+        };
+        let Some(adjustment) = pairs.get(&key) else {
+            return 0.0;
+        };
+        let scale = self.em_size.ppem().unwrap_or(0.0) as f64 / self.units_per_em();
+        *adjustment as f64 * scale
     }
 
     fn can_do_fast_shaping(&self) -> bool {
-        false
+        let font_ref = self.font.get_font_ref();
+        // `read_gpos_pairs` only understands format-1 (non-contextual) pair
+        // positioning, so a `GPOS` table's mere presence — regardless of
+        // whether kerning ended up falling back to `kern` — can mean mark
+        // attachment, contextual, or class-based (format-2) lookups that
+        // fast shaping doesn't run, so it has to disqualify the font here
+        // too, not just `GSUB`.
+        let has_complex_shaping_tables = font_ref.gsub().is_ok() || font_ref.gpos().is_ok();
+        let has_simple_kerning = matches!(self.kerning_data(), KerningData::Kern(_));
+        !has_complex_shaping_tables && has_simple_kerning
     }
 
     fn metrics(&self) -> FontMetrics {
         let metrics = self
-            .test
+            .font
             .get_font_ref()
-            .metrics(self.em_size, LocationRef::default());
+            .metrics(self.em_size, self.location_ref());
         let (underline_thickness, underline_offset) = if let Some(underline) = metrics.underline {
             (underline.thickness, underline.offset)
         } else {
@@ -183,7 +902,7 @@ impl FontHandleMethods for FontHandle {
     }
 
     fn table_for_tag(&self, tag: crate::font::FontTableTag) -> Option<FontTable> {
-        self.test
+        self.font
             .get_font_ref()
             .table_data(Tag::from_u32(tag))
             .map(|data| FontTable {
@@ -195,3 +914,85 @@ impl FontHandleMethods for FontHandle {
         self.font_data.identifier.clone()
     }
 }
+
+/// Rough per-Unicode-block preference list of system font families likely to
+/// cover `codepoint`, tried in order. There's no dedicated font-enumeration
+/// module for the `skrifa` backend yet, so this lives here rather than in a
+/// `super::font_list` that doesn't exist; the block ranges are coarse (not a
+/// full script database) but cover the scripts most commonly missing from a
+/// Latin primary face.
+fn fallback_families_for_codepoint(codepoint: char) -> Vec<String> {
+    let families: &[&str] = match codepoint as u32 {
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF => {
+            &["Noto Sans CJK SC", "PingFang SC", "Microsoft YaHei", "SimSun"]
+        },
+        0x3040..=0x30FF => &["Noto Sans CJK JP", "Hiragino Sans", "MS Gothic"],
+        0xAC00..=0xD7A3 => &["Noto Sans CJK KR", "Apple SD Gothic Neo", "Malgun Gothic"],
+        0x0600..=0x06FF => &["Noto Naskh Arabic", "Geeza Pro", "Tahoma"],
+        0x0590..=0x05FF => &["Noto Sans Hebrew", "New Peninim MT", "Arial"],
+        0x0E00..=0x0E7F => &["Noto Sans Thai", "Thonburi", "Leelawadee UI"],
+        _ => &["Noto Sans Symbols", "Arial Unicode MS", "Segoe UI Symbol"],
+    };
+    families.iter().map(|&name| name.to_owned()).collect()
+}
+
+/// Asks the OS's installed-font catalog (via `font_kit`) for the best match
+/// to `family_name` and loads it into a [`FontTemplateData`]. Returns `None`
+/// if nothing by that name is installed.
+fn system_font_template(family_name: &str) -> Option<Arc<FontTemplateData>> {
+    let handle = font_kit::source::SystemSource::new()
+        .select_best_match(
+            &[font_kit::family_name::FamilyName::Title(family_name.to_owned())],
+            &font_kit::properties::Properties::new(),
+        )
+        .ok()?;
+
+    let font_kit::handle::Handle::Path { path, .. } = handle else {
+        return None;
+    };
+    let bytes = std::fs::read(&path).ok()?;
+    FontTemplateData::new(servo_atoms::Atom::from(path.to_string_lossy().as_ref()), Some(bytes))
+        .ok()
+        .map(Arc::new)
+}
+
+/// Locates a system font covering a codepoint the primary face can't map, so
+/// unsupported scripts fall back to a real glyph instead of tofu.
+///
+/// Per-codepoint results (both hits and misses) are cached, since the same
+/// missing codepoint is typically looked up repeatedly within a run of text.
+#[derive(Debug, Default)]
+pub struct FontFallbackCache {
+    results: Mutex<HashMap<char, Option<(Arc<FontTemplateData>, GlyphId)>>>,
+}
+
+impl FontFallbackCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Walks the per-Unicode-block fallback-family preference list above for
+    /// `codepoint`, building a [`FontHandle`] from the first family whose
+    /// charmap actually covers it.
+    pub fn fallback_for_codepoint(
+        &self,
+        fctx: &FontContextHandle,
+        codepoint: char,
+    ) -> Option<(Arc<FontTemplateData>, GlyphId)> {
+        if let Some(cached) = self.results.lock().unwrap().get(&codepoint) {
+            return cached.clone();
+        }
+
+        let result = fallback_families_for_codepoint(codepoint)
+            .into_iter()
+            .find_map(|family_name| {
+                let template = system_font_template(&family_name)?;
+                let handle = FontHandle::new_from_template(fctx, template.clone(), None).ok()?;
+                let glyph_id = handle.glyph_index(codepoint)?;
+                Some((template, glyph_id))
+            });
+
+        self.results.lock().unwrap().insert(codepoint, result.clone());
+        result
+    }
+}